@@ -2,7 +2,7 @@ use std::fs;
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
-use git::internals::{self, get_log_data};
+use git::internals;
 
 #[allow(dead_code)]
 fn rewrite_input_file(file: &str) {
@@ -13,14 +13,17 @@ fn rewrite_input_file(file: &str) {
         .block_on(async {
             let repository = std::path::Path::new("/home/p4c/apps/git");
             let revision_range = &["--all".to_string(), "--until=01.01.2021".to_string()];
-            let log = internals::get_log(repository, revision_range)
+            let log = internals::get_log(repository, revision_range, &Default::default())
                 .await
-                .expect("get_log failed");
-            fs::write(file, log).expect("Couldn't write a file");
+                .expect("get_log failed")
+                .wait_with_output()
+                .await
+                .expect("git log failed");
+            fs::write(file, log.stdout).expect("Couldn't write a file");
         })
 }
 
-fn get_log_data_benchmark(c: &mut Criterion) {
+fn log_entry_from_record_benchmark(c: &mut Criterion) {
     let benchmark_input = "benches/git_benchmark_input.txt";
     // to update the git_benchmark_input.txt use the call below
     // rewrite_input_file(benchmark_input);
@@ -28,10 +31,63 @@ fn get_log_data_benchmark(c: &mut Criterion) {
     let text = fs::read(benchmark_input).expect("Couldn't read benchmark input");
     let text = String::from_utf8(text).expect("failed on decoding input");
 
-    c.bench_function("get_log_data", |b| {
-        b.iter(|| get_log_data(black_box(&text)))
+    c.bench_function("log_entries_from_record", |b| {
+        b.iter(|| {
+            // cache lives for a single load as in get_log_data
+            let mut refs = internals::RefCache::new();
+            black_box(&text)
+                .split(internals::RECORD_TERMINATOR as char)
+                .flat_map(|record| internals::log_entries_from_record(record, &mut refs))
+                .collect::<Vec<_>>()
+        })
+    });
+}
+
+/// Returns index of field with given placeholder in commit line, graph is the first one
+fn field_index(placeholder: &str) -> usize {
+    let args = internals::log_args(&[], &Default::default());
+    let format = args
+        .iter()
+        .find_map(|a| a.strip_prefix("--format="))
+        .expect("no --format in log args");
+    format
+        .split(internals::FIELD_SEPARATOR)
+        .position(|p| p == placeholder)
+        .expect("placeholder not in format")
+}
+
+/// Decorations (%D) are parsed only for commits which have them, input holds ~63k commits
+fn ref_names_benchmark(c: &mut Criterion) {
+    let text = fs::read_to_string("benches/git_benchmark_input.txt")
+        .expect("Couldn't read benchmark input");
+    let field = field_index("%D");
+    let refs: Vec<&str> = text
+        .split(internals::RECORD_TERMINATOR as char)
+        .filter_map(|record| record.split(internals::FIELD_SEPARATOR).nth(field))
+        .collect();
+
+    c.bench_function("ref_names_from", |b| {
+        b.iter(|| {
+            black_box(&refs)
+                .iter()
+                .map(|r| git::RefNames::from(r))
+                .collect::<Vec<_>>()
+        })
+    });
+    c.bench_function("ref_names_from_cached", |b| {
+        b.iter(|| {
+            let mut cache = internals::RefCache::new();
+            black_box(&refs)
+                .iter()
+                .map(|r| internals::cached_refs(r, &mut cache))
+                .collect::<Vec<_>>()
+        })
     });
 }
 
-criterion_group!(benches, get_log_data_benchmark);
+criterion_group!(
+    benches,
+    log_entry_from_record_benchmark,
+    ref_names_benchmark
+);
 criterion_main!(benches);
@@ -1,47 +1,338 @@
-use std::{path::Path, str::Split};
+use std::{collections::HashMap, path::Path};
 use tokio::{
     fs,
     process::{Child, Command},
 };
 
-use crate::{LogEntry, RefNames};
+use crate::{LogEntry, LogOptions, RefNames};
 
-/// pub functions from this file are only for benchmarking purposes
+// pub functions from this file are only for benchmarking purposes
 // TODO: nothing to benchmark anymore here
 
-pub fn log_entry_from_split(split: &mut Split<&str>) -> LogEntry {
+/// Separates fields of a single commit line.
+/// Assumption: only the subject is free-form text, hashes, dates, refs (control characters are
+/// forbidden in ref names) and encoding never contain it. Subject is therefore put last so any
+/// separator inside it stays part of it.
+pub const FIELD_SEPARATOR: &str = "\x1f";
+/// Terminates a record, passed to git via `-z`. A record holds commit line optionally followed by
+/// graph only lines (e.g. `|\`) separated with a newline.
+pub const RECORD_TERMINATOR: u8 = b'\0';
+/// Number of fields (including graph) in a commit line, see FORMAT.
+const FIELD_COUNT: usize = 11;
+
+/// Refs parsed from %D strings during a single load of the log, identical decorations are parsed
+/// only once.
+pub type RefCache = HashMap<String, Option<RefNames>>;
+
+/// Returns refs for %D string, parsing it only when it's not in cache yet. Commits without refs
+/// (most of them) skip the cache, there's nothing to parse.
+pub fn cached_refs(ref_specs: &str, cache: &mut RefCache) -> Option<RefNames> {
+    if ref_specs.is_empty() {
+        return None;
+    }
+    if let Some(refs) = cache.get(ref_specs) {
+        return refs.clone();
+    }
+    let refs = RefNames::from(ref_specs);
+    cache.insert(ref_specs.to_owned(), refs.clone());
+    refs
+}
+
+/// Rewrites SGR reset without parameter (ESC[m) to explicit ESC[0m, other text is kept as is.
+/// Workaround for https://github.com/uttarayan21/ansi-to-tui/issues/40, remove once it's fixed.
+fn normalize_sgr_reset(graph: &str) -> String {
+    graph.replace("\x1b[m", "\x1b[0m")
+}
+
+/// Parses a single line of git log output, lines without separator hold only graph.
+pub fn log_entry_from_line(line: &str, refs: &mut RefCache) -> LogEntry {
+    let mut split = line.splitn(FIELD_COUNT, FIELD_SEPARATOR);
     LogEntry {
-        // TODO: remove color reset workaround once https://github.com/uttarayan21/ansi-to-tui/issues/40 is fixed
-        graph: String::from(split.next().unwrap()).replace("[m", "[0m"),
+        graph: normalize_sgr_reset(split.next().unwrap()),
         hash: String::from(split.next().unwrap_or("")),
-        subject: String::from(split.next().unwrap_or("")),
         author: String::from(split.next().unwrap_or("")),
         date: String::from(split.next().unwrap_or("")),
-        refs: RefNames::from(split.next().unwrap_or("")),
+        timestamp: split.next().and_then(|t| t.parse().ok()),
+        refs: cached_refs(split.next().unwrap_or(""), refs),
         reached_by: String::from(split.next().unwrap_or("")),
+        encoding: String::from(split.next().unwrap_or("")),
+        parents: split
+            .next()
+            .unwrap_or("")
+            .split_whitespace()
+            .map(String::from)
+            .collect(),
+        notes: String::from(split.next().unwrap_or("").trim_end_matches('\n')),
+        subject: String::from(split.next().unwrap_or("")),
     }
 }
 
-pub async fn get_log<'a>(
+/// Parses a NUL terminated record which yields commit entry followed by graph only entries.
+/// Commit line continues on following lines while it has less than FIELD_COUNT fields (notes are
+/// multi-line), git draws graph in front of each of them which is dropped.
+pub fn log_entries_from_record(
+    record: &str,
+    refs: &mut RefCache,
+) -> impl Iterator<Item = LogEntry> {
+    let mut lines = record.trim_end_matches('\n').split('\n');
+    let mut entries = Vec::new();
+    if let Some(first) = lines.next() {
+        let mut line = first.to_owned();
+        let graph = first.split(FIELD_SEPARATOR).next().unwrap_or("");
+        let width = visible_len(graph);
+        while line.contains(FIELD_SEPARATOR)
+            && line.matches(FIELD_SEPARATOR).count() < FIELD_COUNT - 1
+        {
+            let Some(next) = lines.next() else {
+                break;
+            };
+            line.push('\n');
+            line.push_str(strip_graph(next, width));
+        }
+        entries.push(log_entry_from_line(&line, refs));
+    }
+    entries.extend(lines.map(|line| log_entry_from_line(line, refs)));
+    entries.into_iter()
+}
+
+/// Returns count of chars of graph outside of SGR escape sequences (`ESC [ ... m`)
+fn visible_len(graph: &str) -> usize {
+    let mut len = 0;
+    let mut chars = graph.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            len += 1;
+        }
+    }
+    len
+}
+
+/// Drops graph of given visible width from start of line, escape sequences in it included
+fn strip_graph(line: &str, width: usize) -> &str {
+    let mut visible = 0;
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&(_, c)| c == 'm');
+        } else if visible == width {
+            return &line[i..];
+        } else {
+            visible += 1;
+        }
+    }
+    ""
+}
+
+/// Returns arguments of git-log (without the binary) used to load the log.
+pub fn log_args(revision_range: &[String], options: &LogOptions) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "log",
+        "--graph",
+        "--color=always",
+        "--oneline",
+        "-z",
+        "--decorate=full", // full decoration needed for refs/tags, refs/remotes etc.
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    // %S for which command line ref reached that commit
+    // %D refs
+    // %ad honors --date while %ar is always relative
+    // %at author date as unix timestamp
+    // %e encoding
+    // %P parents
+    // %N notes, may span several lines, see log_entries_from_record
+    // %s subject, has to be last, see FIELD_SEPARATOR
+    args.push(
+        [
+            "--format=",
+            "%H",
+            "%aN",
+            if options.date.is_some() { "%ad" } else { "%ar" },
+            "%at",
+            "%D",
+            "%S",
+            "%e",
+            "%P",
+            "%N",
+            "%s",
+        ]
+        .join(FIELD_SEPARATOR),
+    );
+    if let Some(date) = &options.date {
+        args.push(format!("--date={}", date));
+    }
+    if options.no_merges {
+        args.push("--no-merges".to_owned());
+    }
+    if options.first_parent {
+        args.push("--first-parent".to_owned());
+    }
+    if let Some(author) = &options.author {
+        args.push(format!("--author={}", author));
+    }
+    args.extend(options.grep.iter().map(|p| format!("--grep={}", p)));
+    if options.all_match {
+        args.push("--all-match".to_owned());
+    }
+    if options.ignore_case {
+        args.push("--regexp-ignore-case".to_owned());
+    }
+    args.extend(revision_range.iter().cloned());
+    if !options.paths.is_empty() {
+        args.push("--".to_owned());
+        args.extend(options.paths.iter().cloned());
+    }
+    args
+}
+
+pub async fn get_log(
     repository: &Path,
     revision_range: &[String],
+    options: &LogOptions,
 ) -> Result<Child, std::io::Error> {
     let repository = fs::canonicalize(repository).await?;
     let child = Command::new("git")
         .kill_on_drop(true)
         .current_dir(repository)
-        .args([
-            "log",
-            "--graph",
-            "--color=always",
-            "--oneline",
-            "--decorate=full", // full decoration needed for refs/tags, refs/remotes etc.
-        ])
-        // %S for which command line ref reached that commit
-        // %D refs
-        .arg("--format=\x1f%H\x1f%s\x1f%aN\x1f%ar\x1f%D\x1f%S")
-        .args(revision_range)
+        .args(log_args(revision_range, options))
         .stdout(std::process::Stdio::piped())
+        // read once stdout is done so errors don't mess up the terminal
+        .stderr(std::process::Stdio::piped())
         .spawn()?;
     Ok(child)
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        log_args, log_entries_from_record, log_entry_from_line, normalize_sgr_reset, RefCache,
+    };
+    use crate::LogOptions;
+
+    #[test]
+    fn subject_with_separator() {
+        let entry = log_entry_from_line(
+            "*\x1fabc\x1fauthor\x1fdate\x1f1700000000\x1f\x1f\x1f\x1fp1 p2\x1f\x1fsubject \x1f with\x1f separator",
+            &mut RefCache::new(),
+        );
+        assert_eq!(entry.graph, "*");
+        assert_eq!(entry.hash, "abc");
+        assert_eq!(entry.author, "author");
+        assert_eq!(entry.date, "date");
+        assert_eq!(entry.timestamp, Some(1700000000));
+        assert_eq!(entry.parents, ["p1", "p2"]);
+        assert_eq!(entry.subject, "subject \x1f with\x1f separator");
+    }
+
+    #[test]
+    fn sgr_reset() {
+        assert_eq!(
+            normalize_sgr_reset("\x1b[31m|\x1b[m \x1b[32m*\x1b[m"),
+            "\x1b[31m|\x1b[0m \x1b[32m*\x1b[0m"
+        );
+        // only escape sequences are touched
+        assert_eq!(normalize_sgr_reset("[m| [0m"), "[m| [0m");
+
+        let entry = log_entry_from_line(
+            "\x1b[31m*\x1b[m\x1fabc\x1f\x1f\x1f\x1f\x1f\x1f\x1f\x1f\x1f[m subject",
+            &mut RefCache::new(),
+        );
+        assert_eq!(entry.graph, "\x1b[31m*\x1b[0m");
+        assert_eq!(entry.subject, "[m subject");
+    }
+
+    #[test]
+    fn args() {
+        let options = LogOptions {
+            date: Some("short".to_owned()),
+            no_merges: true,
+            author: Some("Jane Doe".to_owned()),
+            grep: vec!["fix".to_owned(), "parser".to_owned()],
+            all_match: true,
+            ignore_case: true,
+            paths: vec!["src".to_owned()],
+            ..Default::default()
+        };
+        let args = log_args(&["main..topic".to_owned()], &options);
+        assert_eq!(args[0], "log");
+        assert!(args
+            .iter()
+            .any(|a| a.starts_with("--format=") && a.contains("%ad")));
+        assert_eq!(
+            args[args.len() - 10..],
+            [
+                "--date=short",
+                "--no-merges",
+                "--author=Jane Doe",
+                "--grep=fix",
+                "--grep=parser",
+                "--all-match",
+                "--regexp-ignore-case",
+                "main..topic",
+                "--",
+                "src"
+            ]
+        );
+
+        let args = log_args(&[], &Default::default());
+        assert!(args.iter().any(|a| a.contains("%ar")));
+        assert!(!args.iter().any(|a| a == "--"));
+    }
+
+    #[test]
+    fn record_with_graph_lines() {
+        let entries: Vec<_> = log_entries_from_record(
+            "*\x1fabc\x1fauthor\x1fdate\x1f\x1f\x1f\x1f\x1fp1\x1f\x1fsubject\n|\\  \n",
+            &mut RefCache::new(),
+        )
+        .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].hash, "abc");
+        assert_eq!(entries[0].subject, "subject");
+        assert_eq!(entries[1].graph, "|\\  ");
+        assert!(entries[1].hash.is_empty());
+        assert_eq!(entries[1].timestamp, None);
+    }
+
+    #[test]
+    fn refs_parsed_once_per_cache() {
+        let mut refs = RefCache::new();
+        let line = "*\x1fabc\x1f\x1f\x1f\x1fHEAD -> refs/heads/main, tag: refs/tags/v1\x1f";
+        let first = log_entry_from_line(line, &mut refs);
+        let second = log_entry_from_line(line, &mut refs);
+        log_entry_from_line("|", &mut refs);
+        assert_eq!(refs.len(), 1);
+        for entry in [first, second] {
+            let names = entry.refs.unwrap();
+            assert_eq!(names.head.as_deref(), Some("main"));
+            assert_eq!(names.tags, ["v1"]);
+        }
+    }
+
+    #[test]
+    fn record_with_multi_line_notes() {
+        // as printed by git for a commit on the side branch of a merge
+        let record =
+            "\x1b[31m|\x1b[m * \x1fabc\x1fauthor\x1fdate\x1f\x1f\x1f\x1f\x1fp1\x1fline one\n\
+            \x1b[31m|\x1b[m | line two\n\x1b[31m|\x1b[m | \x1fsubject\n\
+            \x1b[31m|\x1b[m/  \n";
+        let entries: Vec<_> = log_entries_from_record(record, &mut RefCache::new()).collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].graph, "\x1b[31m|\x1b[0m * ");
+        assert_eq!(entries[0].hash, "abc");
+        assert_eq!(entries[0].notes, "line one\nline two");
+        assert_eq!(entries[0].subject, "subject");
+        assert_eq!(entries[1].graph, "\x1b[31m|\x1b[0m/  ");
+
+        let entries: Vec<_> = log_entries_from_record(
+            "*\x1fabc\x1fauthor\x1fdate\x1f\x1f\x1f\x1f\x1fp1\x1f\x1fsubject",
+            &mut RefCache::new(),
+        )
+        .collect();
+        assert!(entries[0].notes.is_empty());
+    }
+}
@@ -1,41 +1,271 @@
 pub mod internals;
 mod log_entry;
+mod log_options;
 mod ref_names;
+mod remote;
+#[cfg(any(test, feature = "test-repo"))]
+pub mod test_repo;
 
 use std::path::Path;
 
 use async_stream::stream;
+pub use internals::log_args;
 use log::warn;
 pub use log_entry::LogEntry;
-pub use ref_names::RefNames;
-use tokio::io::{AsyncBufReadExt, BufReader};
+pub use log_options::{is_valid_date_format, LogOptions};
+pub use ref_names::{short_ref_name, RefNames};
+pub use remote::web_url;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
 use tokio_stream::Stream;
 
 /// Produces a stream of LogEntry for given repository and revision_range.
 /// This stream may be used in async manner to allow quick and responsive UI for big amount of
-/// elements.
-#[allow(clippy::single_char_pattern)] // broken compilation after suggested fix
+/// elements. Failure of git (non-zero exit status with its stderr) is the last item of the stream.
 pub async fn get_log_data(
     repository: &Path,
     revision_range: &[String],
-) -> Result<impl Stream<Item = LogEntry>, std::io::Error> {
-    let mut child = internals::get_log(repository, revision_range).await?;
+    options: &LogOptions,
+) -> Result<impl Stream<Item = Result<LogEntry, std::io::Error>>, std::io::Error> {
+    let mut child = internals::get_log(repository, revision_range, options).await?;
 
     let stdout = child
         .stdout
         .take()
         .expect("git log did not output anything");
+    let mut stderr = child.stderr.take().expect("git log stderr is not piped");
     let reader = BufReader::new(stdout);
-    let mut lines = reader.lines();
+    let mut records = reader.split(internals::RECORD_TERMINATOR);
 
     let s = stream! {
-        while let Some(line) = lines.next_line().await.unwrap() {
-            yield internals::log_entry_from_split(&mut line.split("\x1f"));
+        let mut refs = internals::RefCache::new();
+        loop {
+            match records.next_segment().await {
+                Ok(Some(record)) => {
+                    for entry in internals::log_entries_from_record(&String::from_utf8_lossy(&record), &mut refs) {
+                        yield Ok(entry);
+                    }
+                }
+                Ok(None) => break,
+                Err(error) => {
+                    yield Err(error);
+                    break;
+                }
+            }
         }
 
-        // handle failure?
-        let status = child.wait().await;
-        warn!("Process exited with: {:?}", status);
+        let mut message = String::new();
+        if let Err(error) = stderr.read_to_string(&mut message).await {
+            warn!("Could not read git log stderr: {}", error);
+        }
+        match child.wait().await {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                warn!("Process exited with: {:?}", status);
+                let message = message.trim();
+                yield Err(std::io::Error::other(if message.is_empty() {
+                    format!("git log exited with: {status}")
+                } else {
+                    message.to_owned()
+                }));
+            }
+            Err(error) => yield Err(error),
+        }
     };
     Ok(s)
 }
+
+/// Runs git with given args and returns its stdout, stderr is returned as error on failure.
+/// This is a blocking call meant for on-demand usage from commands.
+fn git_output<I, S>(repository: &Path, args: I) -> Result<String, std::io::Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    let output = std::process::Command::new("git")
+        .current_dir(repository)
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns output of git-show (stat, patch and notes, git shows them by default) for given commit.
+/// Non empty paths limit the output to given pathspecs.
+pub fn get_show(repository: &Path, sha: &str, paths: &[&str]) -> Result<String, std::io::Error> {
+    let args = ["show", "--stat", "--patch", sha, "--"];
+    git_output(repository, args.iter().chain(paths))
+}
+
+/// Returns top-level directory of the working tree containing given path.
+pub fn get_toplevel(repository: &Path) -> Result<String, std::io::Error> {
+    let toplevel = git_output(repository, ["rev-parse", "--show-toplevel"])?;
+    Ok(toplevel.trim_end().to_owned())
+}
+
+/// Returns full sha of given revision.
+pub fn rev_parse(repository: &Path, revision: &str) -> Result<String, std::io::Error> {
+    let sha = git_output(repository, ["rev-parse", "--verify", revision])?;
+    Ok(sha.trim_end().to_owned())
+}
+
+/// Returns abbreviated sha of given revision, as git writes it e.g. in rebase todo list.
+pub fn abbreviate(repository: &Path, revision: &str) -> Result<String, std::io::Error> {
+    let sha = git_output(repository, ["rev-parse", "--short", revision])?;
+    Ok(sha.trim_end().to_owned())
+}
+
+/// Returns configured URL of given remote.
+pub fn get_remote_url(repository: &Path, remote: &str) -> Result<String, std::io::Error> {
+    let url = git_output(repository, ["remote", "get-url", remote])?;
+    Ok(url.trim_end().to_owned())
+}
+
+/// Returns (ahead, behind) counts of HEAD against its upstream, None when there's no upstream
+/// (or HEAD is detached).
+pub async fn get_ahead_behind(repository: &Path) -> Option<(usize, usize)> {
+    let output = tokio::process::Command::new("git")
+        .current_dir(repository)
+        .args(["rev-list", "--left-right", "--count", "HEAD...@{upstream}"])
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_left_right_count(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_left_right_count(output: &str) -> Option<(usize, usize)> {
+    let mut counts = output.split_whitespace().map(str::parse);
+    match (counts.next(), counts.next(), counts.next()) {
+        (Some(Ok(ahead)), Some(Ok(behind)), None) => Some((ahead, behind)),
+        _ => None,
+    }
+}
+
+/// Returns full commit message (subject, blank line and body) for given commit.
+/// Line endings are normalized and trailing whitespace is trimmed.
+pub fn get_message(repository: &Path, sha: &str) -> Result<String, std::io::Error> {
+    let message = git_output(repository, ["show", "--no-patch", "--format=%B", sha])?;
+    Ok(normalize_message(&message))
+}
+
+/// Returns git-blame output for path as of given commit.
+pub fn get_blame(repository: &Path, sha: &str, path: &str) -> Result<String, std::io::Error> {
+    git_output(repository, ["blame", sha, "--", path])
+}
+
+/// Restores path in working tree (not in index) from given commit, eg. path which didn't exist
+/// there fails with git's message.
+pub fn restore_file(repository: &Path, sha: &str, path: &str) -> Result<(), std::io::Error> {
+    git_output(repository, ["restore", "--source", sha, "--", path])?;
+    Ok(())
+}
+
+/// Returns value of encoding header of given commit, empty for the default (UTF-8).
+pub fn get_encoding(repository: &Path, sha: &str) -> Result<String, std::io::Error> {
+    let encoding = git_output(repository, ["show", "--no-patch", "--format=%e", sha])?;
+    Ok(encoding.trim_end().to_owned())
+}
+
+/// Returns notes of given commit, empty when it has none.
+pub fn get_notes(repository: &Path, sha: &str) -> Result<String, std::io::Error> {
+    let notes = git_output(repository, ["show", "--no-patch", "--format=%N", sha])?;
+    Ok(notes.trim_end().to_owned())
+}
+
+fn normalize_message(message: &str) -> String {
+    message
+        .lines() // handles \r\n too
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use tokio_stream::StreamExt;
+
+    use super::{
+        get_blame, get_log_data, get_notes, normalize_message, parse_left_right_count,
+        test_repo::TestRepo, LogOptions,
+    };
+
+    #[test]
+    fn left_right_count() {
+        assert_eq!(parse_left_right_count("3\t5\n"), Some((3, 5)));
+        assert_eq!(parse_left_right_count("0\t0"), Some((0, 0)));
+        assert_eq!(parse_left_right_count(""), None);
+        assert_eq!(parse_left_right_count("3"), None);
+        assert_eq!(parse_left_right_count("a\tb"), None);
+    }
+
+    #[tokio::test]
+    async fn log_limited_to_paths() {
+        let repo = TestRepo::new("log-paths");
+        let first = repo.commit("src/main.rs", "fn main() {}", "Add main");
+        repo.commit("README.md", "glog", "Add readme");
+        let second = repo.commit("src/main.rs", "fn main() {}\n", "Fix main");
+        let options = LogOptions {
+            paths: vec!["src".to_owned()],
+            ..Default::default()
+        };
+        let entries: Vec<_> = get_log_data(repo.path(), &[], &options)
+            .await
+            .expect("git log failed")
+            .collect()
+            .await;
+        let hashes: Vec<_> = entries
+            .into_iter()
+            .map(|e| e.expect("git log failed"))
+            .filter(|e| !e.hash.is_empty())
+            .map(|e| e.hash)
+            .collect();
+        assert_eq!(hashes, [second, first]);
+    }
+
+    #[tokio::test]
+    async fn log_with_notes() {
+        let repo = TestRepo::new("log-notes");
+        let sha = repo.commit("a", "a", "Add a");
+        repo.git(["notes", "add", "-m", "line one\nline two", &sha]);
+        let entries: Vec<_> = get_log_data(repo.path(), &[], &Default::default())
+            .await
+            .expect("git log failed")
+            .collect()
+            .await;
+        let entry = entries[0].as_ref().expect("git log failed");
+        assert_eq!(entry.notes, "line one\nline two");
+        assert_eq!(entry.subject, "Add a");
+        assert_eq!(get_notes(repo.path(), &sha).unwrap(), "line one\nline two");
+    }
+
+    #[test]
+    fn blame() {
+        let repo = TestRepo::new("blame");
+        repo.commit("Cargo.toml", "[package]\n", "Add manifest");
+        let blame = get_blame(repo.path(), "HEAD", "Cargo.toml").expect("git blame failed");
+        assert!(blame.contains("Jane Doe"), "{blame}");
+        assert!(blame.contains("[package]"), "{blame}");
+        let error = get_blame(repo.path(), "HEAD", "no/such/file").unwrap_err();
+        assert!(error.to_string().contains("no/such/file"), "{error}");
+    }
+
+    #[test]
+    fn message_normalization() {
+        assert_eq!(
+            normalize_message("subject  \r\n\r\nbody line\t\r\nsecond\n\n\n"),
+            "subject\n\nbody line\nsecond"
+        );
+        assert_eq!(normalize_message("subject\n"), "subject");
+        assert_eq!(normalize_message(""), "");
+    }
+}
@@ -1,15 +1,23 @@
-use crate::ref_names::RefNames;
+use crate::ref_names::{short_ref_name, RefNames};
 
-#[derive(Debug, Clone)] // TODO: remove clone!
+#[derive(Debug, Clone, Default)] // TODO: remove clone!
 pub struct LogEntry {
     pub graph: String,
     pub hash: String,
     pub subject: String,
     pub author: String,
     pub date: String,
+    /// Author date as unix timestamp, None for graph only entries
+    pub timestamp: Option<i64>,
     pub refs: Option<RefNames>,
     /// Command line ref via which this commit was reached
     pub reached_by: String,
+    /// Value of commit encoding header, empty for default (UTF-8)
+    pub encoding: String,
+    /// Parent hashes, more than one for merge commits
+    pub parents: Vec<String>,
+    /// Notes of the default notes ref, lines joined with `\n`, empty when there are none
+    pub notes: String,
 }
 
 impl LogEntry {
@@ -20,4 +28,111 @@ impl LogEntry {
             format!("({}, {})", self.author, self.date)
         }
     }
+
+    /// reached_by for display and grouping, without refs/... prefix
+    pub fn reached_by_short(&self) -> &str {
+        short_ref_name(&self.reached_by)
+    }
+
+    /// Drops fields not needed to display the entry in a list (parents, reached_by, encoding and
+    /// notes)
+    /// and releases excess capacity. Parents are the biggest of them, one 40 chars String each,
+    /// so this saves roughly a third of entry size; the rest can be fetched per commit.
+    pub fn compact(&mut self) {
+        self.parents = Vec::new();
+        self.reached_by = String::new();
+        self.encoding = String::new();
+        self.notes = String::new();
+        for field in [
+            &mut self.graph,
+            &mut self.hash,
+            &mut self.subject,
+            &mut self.author,
+            &mut self.date,
+        ] {
+            field.shrink_to_fit();
+        }
+    }
+
+    pub fn is_merge(&self) -> bool {
+        self.parents.len() > 1
+    }
+
+    /// Expands git-log like placeholders in template using this entry.
+    /// Supported: %h (abbreviated hash), %H (hash), %s (subject), %an (author), %ad (date), %%.
+    /// Unknown placeholders are left untouched.
+    pub fn format(&self, template: &str) -> String {
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(idx) = rest.find('%') {
+            result.push_str(&rest[..idx]);
+            rest = &rest[idx..];
+            let (value, len) = if rest.starts_with("%an") {
+                (self.author.as_str(), 3)
+            } else if rest.starts_with("%ad") {
+                (self.date.as_str(), 3)
+            } else if rest.starts_with("%h") {
+                (&self.hash[..std::cmp::min(8, self.hash.len())], 2)
+            } else if rest.starts_with("%H") {
+                (self.hash.as_str(), 2)
+            } else if rest.starts_with("%s") {
+                (self.subject.as_str(), 2)
+            } else if rest.starts_with("%%") {
+                ("%", 2)
+            } else {
+                ("%", 1)
+            };
+            result.push_str(value);
+            rest = &rest[len..];
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LogEntry;
+
+    #[test]
+    fn format() {
+        let entry = LogEntry {
+            graph: "* ".to_owned(),
+            hash: "470ff134604ae1fbc7916fdddc87fb51514b8897".to_owned(),
+            subject: "MaintNotes: update".to_owned(),
+            author: "Junio C Hamano".to_owned(),
+            date: "1 year ago".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(
+            entry.format(r#"%h ("%s")"#),
+            r#"470ff134 ("MaintNotes: update")"#
+        );
+        assert_eq!(
+            entry.format("%H by %an, %ad"),
+            "470ff134604ae1fbc7916fdddc87fb51514b8897 by Junio C Hamano, 1 year ago"
+        );
+        assert_eq!(entry.format("100%% %x %"), "100% %x %");
+    }
+
+    #[test]
+    fn compact() {
+        let mut entry = LogEntry {
+            hash: "m".to_owned(),
+            subject: String::with_capacity(64) + "subject",
+            reached_by: "refs/heads/main".to_owned(),
+            encoding: "ISO-8859-1".to_owned(),
+            notes: "note".to_owned(),
+            parents: vec!["a".to_owned(), "b".to_owned()],
+            ..Default::default()
+        };
+        entry.compact();
+        assert_eq!(entry.subject, "subject");
+        assert_eq!(entry.subject.capacity(), "subject".len());
+        assert_eq!(entry.hash, "m");
+        assert!(entry.parents.is_empty());
+        assert!(entry.reached_by.is_empty());
+        assert!(entry.encoding.is_empty());
+        assert!(entry.notes.is_empty());
+    }
 }
@@ -0,0 +1,66 @@
+/// Options for git-log which are not part of the revision range.
+#[derive(Debug, Default, Clone)]
+pub struct LogOptions {
+    /// Value for git-log `--date=<format>`. When set the date column uses `%ad` (which honors
+    /// `--date`), otherwise `%ar` is used which is always relative.
+    pub date: Option<String>,
+    /// Adds `--no-merges` so merge commits are hidden.
+    pub no_merges: bool,
+    /// Adds `--first-parent` so merged branches are not followed.
+    pub first_parent: bool,
+    /// Value for git-log `--author=<pattern>` (regular expression) so only commits of matching
+    /// authors are listed.
+    pub author: Option<String>,
+    /// Values for git-log `--grep=<pattern>`, commits with message matching any of them (all with
+    /// all_match) are listed.
+    pub grep: Vec<String>,
+    /// Adds `--all-match` so commits have to match all grep patterns.
+    pub all_match: bool,
+    /// Adds `--regexp-ignore-case`, it applies to both grep and author patterns.
+    pub ignore_case: bool,
+    /// Pathspecs passed after `--` so only commits touching them are listed.
+    pub paths: Vec<String>,
+}
+
+const DATE_FORMATS: &[&str] = &[
+    "default",
+    "relative",
+    "local",
+    "iso",
+    "iso8601",
+    "iso-strict",
+    "iso8601-strict",
+    "rfc",
+    "rfc2822",
+    "short",
+    "raw",
+    "human",
+    "unix",
+];
+
+/// Checks if given value is accepted by git-log `--date`.
+/// Known formats may have `-local` suffix and `format:` strings are passed verbatim.
+pub fn is_valid_date_format(format: &str) -> bool {
+    if format.starts_with("format:") || format.starts_with("format-local:") {
+        return true;
+    }
+    let format = format.strip_suffix("-local").unwrap_or(format);
+    DATE_FORMATS.contains(&format)
+}
+
+#[cfg(test)]
+mod test {
+    use super::is_valid_date_format;
+
+    #[test]
+    fn date_formats() {
+        assert!(is_valid_date_format("short"));
+        assert!(is_valid_date_format("iso-strict"));
+        assert!(is_valid_date_format("relative-local"));
+        assert!(is_valid_date_format("format:%Y-%m-%d %H:%M"));
+        assert!(is_valid_date_format("format-local:%c"));
+        assert!(!is_valid_date_format("yesterday"));
+        assert!(!is_valid_date_format(""));
+        assert!(!is_valid_date_format("-local"));
+    }
+}
@@ -10,6 +10,17 @@ pub struct RefNames {
     pub remotes: Vec<String>,
 }
 
+/// Prefixes stripped for display, same as RefNames::from strips from decorations
+const REF_PREFIXES: &[&str] = &["refs/heads/", "refs/tags/", "refs/remotes/", "refs/"];
+
+/// Returns ref name without its `refs/...` prefix, any other value (eg. sha, HEAD) is returned as is.
+pub fn short_ref_name(name: &str) -> &str {
+    REF_PREFIXES
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(name)
+}
+
 impl RefNames {
     pub fn from(ref_specs: &str) -> Option<Self> {
         if ref_specs.is_empty() {
@@ -57,3 +68,23 @@ impl RefNames {
         Some(refs)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::short_ref_name;
+
+    #[test]
+    fn short_names() {
+        assert_eq!(short_ref_name("refs/heads/main"), "main");
+        assert_eq!(short_ref_name("refs/heads/feature/x"), "feature/x");
+        assert_eq!(short_ref_name("refs/tags/v1.0"), "v1.0");
+        assert_eq!(short_ref_name("refs/remotes/origin/main"), "origin/main");
+        assert_eq!(short_ref_name("refs/stash"), "stash");
+        assert_eq!(
+            short_ref_name("d401a57c0ffee0d401a57c0ffee0d401a57c0ffe"),
+            "d401a57c0ffee0d401a57c0ffee0d401a57c0ffe"
+        );
+        assert_eq!(short_ref_name("HEAD"), "HEAD");
+        assert_eq!(short_ref_name(""), "");
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Git repository created in temporary directory for tests, so they don't depend on history of
+/// the checkout they run in. Removed on drop.
+pub struct TestRepo {
+    path: PathBuf,
+}
+
+impl TestRepo {
+    /// Creates empty repository with `main` branch, name has to be unique among tests
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("glog-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("failed to create test repository");
+        let repo = Self { path };
+        repo.git(["init", "-q", "-b", "main"]);
+        repo
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns command run in repository, it doesn't see user's git config
+    pub fn command(&self, program: &str) -> Command {
+        let mut command = Command::new(program);
+        command
+            .current_dir(&self.path)
+            .env("GIT_CONFIG_GLOBAL", "/dev/null")
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_AUTHOR_NAME", "Jane Doe")
+            .env("GIT_AUTHOR_EMAIL", "jane@example.com")
+            .env("GIT_AUTHOR_DATE", "2024-02-29T13:05:00Z")
+            .env("GIT_COMMITTER_NAME", "Jane Doe")
+            .env("GIT_COMMITTER_EMAIL", "jane@example.com")
+            .env("GIT_COMMITTER_DATE", "2024-02-29T13:05:00Z");
+        command
+    }
+
+    /// Runs git with given arguments, panics when it fails, returns its trimmed output
+    pub fn git<I, S>(&self, args: I) -> String
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let output = self
+            .command("git")
+            .args(args)
+            .output()
+            .expect("failed to run git");
+        assert!(
+            output.status.success(),
+            "git failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_owned()
+    }
+
+    /// Writes file, commits it with given message and returns sha of the commit
+    pub fn commit(&self, file: &str, content: &str, message: &str) -> String {
+        let path = self.path.join(file);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).expect("failed to create directory");
+        }
+        std::fs::write(path, content).expect("failed to write file");
+        self.git(["add", file]);
+        self.git(["commit", "-q", "-m", message]);
+        self.git(["rev-parse", "HEAD"])
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+
+/// Scales step of repeated navigation actions, e.g. when `j` is held down.
+/// Each repetition of the same action within `window` doubles the step up to `max_step`,
+/// any other action or a pause starts again from 1.
+#[derive(Debug)]
+pub struct Acceleration {
+    pub enabled: bool,
+    pub window: Duration,
+    pub max_step: usize,
+    last: Option<(String, Instant)>,
+    step: usize,
+}
+
+impl Default for Acceleration {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window: Duration::from_millis(100),
+            max_step: 8,
+            last: None,
+            step: 1,
+        }
+    }
+}
+
+impl Acceleration {
+    /// Returns step for action fired at given time
+    pub fn step(&mut self, action: &str, now: Instant) -> usize {
+        if !self.enabled {
+            return 1;
+        }
+        let repeated = self.last.as_ref().is_some_and(|(last_action, last_time)| {
+            last_action == action && now.saturating_duration_since(*last_time) <= self.window
+        });
+        self.step = if repeated {
+            std::cmp::min(self.step * 2, self.max_step)
+        } else {
+            1
+        };
+        self.last = Some((action.to_owned(), now));
+        self.step
+    }
+
+    /// Forgets last action so next step starts from 1
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.step = 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::Acceleration;
+
+    fn enabled() -> Acceleration {
+        Acceleration {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let mut accel = Acceleration::default();
+        let now = Instant::now();
+        assert_eq!(accel.step("down", now), 1);
+        assert_eq!(accel.step("down", now), 1);
+    }
+
+    #[test]
+    fn doubles_up_to_max() {
+        let mut accel = enabled();
+        let start = Instant::now();
+        let steps: Vec<_> = (0..6)
+            .map(|i| accel.step("down", start + Duration::from_millis(50 * i)))
+            .collect();
+        assert_eq!(steps, [1, 2, 4, 8, 8, 8]);
+    }
+
+    #[test]
+    fn pause_and_other_action_restart() {
+        let mut accel = enabled();
+        let start = Instant::now();
+        assert_eq!(accel.step("down", start), 1);
+        assert_eq!(accel.step("down", start + Duration::from_millis(50)), 2);
+        // pause longer than window
+        assert_eq!(accel.step("down", start + Duration::from_millis(500)), 1);
+        assert_eq!(accel.step("down", start + Duration::from_millis(550)), 2);
+        // direction change
+        assert_eq!(accel.step("up", start + Duration::from_millis(600)), 1);
+        assert_eq!(accel.step("up", start + Duration::from_millis(650)), 2);
+
+        accel.reset();
+        assert_eq!(accel.step("up", start + Duration::from_millis(700)), 1);
+    }
+}
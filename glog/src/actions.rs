@@ -1,4 +1,4 @@
-
+use std::{collections::BTreeMap, process::ExitStatus};
 
 use copypasta::{x11_clipboard::X11ClipboardContext, ClipboardProvider};
 use log::debug;
@@ -36,28 +36,91 @@ pub fn actions<'a>() -> Vec<(&'static str, FnCommand<Context<'a>>)> {
         ("actions", list_actions),
         ("map", map_action),
         ("unmap", unmap_action),
+        ("maps", list_maps),
         ("echo", echo),
+        ("explain_key", explain_key),
         ("quit", quit),
+        ("dismiss", dismiss),
         ("up", up),
         ("down", down),
         ("pageup", page_up),
         ("pagedown", page_down),
+        ("scroll_left", scroll_left),
+        ("scroll_right", scroll_right),
+        ("scroll_home", scroll_home),
         ("top", top),
         ("bottom", bottom),
         ("nodeup", node_up),
         ("nodedown", node_down),
         ("center", node_center),
+        ("goto_head", goto_head),
+        ("goto_child", goto_child),
+        ("goto_line", goto_line),
+        ("mark", mark),
+        ("goto_mark", goto_mark),
+        ("mergeup", merge_up),
+        ("mergedown", merge_down),
+        ("tagup", tag_up),
+        ("tagdown", tag_down),
         ("yank", yank),
+        ("yank_ref_line", yank_ref_line),
+        ("yank_message", yank_message),
+        ("yank_diff", yank_diff),
+        ("yank_repo", yank_repo),
+        ("yank_compare", yank_compare),
+        ("yank_hyperlink", yank_hyperlink),
+        ("yank_selected", yank_selected),
+        ("pwd", pwd),
+        ("gitcmd", git_command),
+        ("shas", shas),
+        ("yank_gitcmd", yank_git_command),
         ("select", select),
+        ("toggle_full_hash", toggle_full_hash),
+        ("toggle_merges", toggle_merges),
+        ("toggle_first_parent", toggle_first_parent),
+        ("toggle_fold", toggle_fold),
+        ("toggle_group", toggle_group),
+        ("toggle_wrap", toggle_wrap),
+        ("reset_view", reset_view),
         ("mode", set_mode),
+        ("feedkeys", feed_keys),
+        ("macro_record", macro_record),
+        ("macro_play", macro_play),
         ("status", status),
+        ("set", set_option),
         ("exec", exec),
+        ("exec_reload", exec_reload),
+        ("exec_show", exec_show),
+        ("gui", gui),
+        ("diff_worktree", diff_worktree),
+        ("diff_prev_visited", diff_prev_visited),
+        ("restore_file", restore_file),
+        ("reword", reword),
+        ("reword_head", reword_head),
         ("search", search),
         ("reload", reload),
+        ("paths", set_paths),
+        ("author_filter", author_filter),
+        ("grep", grep),
+        ("show", show),
+        ("review", review),
+        ("blame", blame),
+        ("refresh_diff", refresh_diff),
         ("enter_reload", enter_reload),
     ]
 }
 
+/// Commands which change repository state and need confirmation
+pub fn destructive_actions() -> &'static [&'static str] {
+    &["reword", "reword_head", "restore_file"]
+}
+
+/// Commands running shell commands expected to change repository (`!!` prefix), they are not
+/// confirmed but read-only mode refuses them as destructive ones
+pub fn writing_actions() -> &'static [&'static str] {
+    &["exec_reload"]
+}
+
 impl Context<'_> {
     pub fn render(&mut self) -> Result<(), String> {
         self.term
@@ -67,17 +130,31 @@ impl Context<'_> {
         Ok(())
     }
 
-    fn call_in_shell(&mut self, cmd: String) -> Result<(), std::io::Error> {
+    fn yank_text(&mut self, text: String) -> CommandResult {
+        let result = match self.clipboard.as_mut() {
+            Some(clipboard) => clipboard
+                .set_contents(text.clone())
+                .map_err(|e| format!("Clipboard error: {e}")),
+            None if self.app.osc52 => {
+                let sequence = crate::osc52::sequence(&text)?;
+                self.term
+                    .write_raw(&sequence)
+                    .map_err(|e| format!("Terminal write failed with: {e}"))
+            }
+            None => Err("No clipboard provider! (see :set osc52=on)".to_owned()),
+        };
+        self.app.status = format!("yanked: {}", text);
+        result
+    }
+
+    fn call_in_shell(&mut self, cmd: String) -> Result<ExitStatus, std::io::Error> {
         // TODO: add info to help about SHELL
         let shell = std::env::var("SHELL").unwrap_or("bash".into());
         let mut command = std::process::Command::new(shell);
         command
             .current_dir(self.app.repository_path())
             .args(["-c", &cmd]);
-        self.term
-            .call_external(command)
-            .expect("Something went wrong");
-        Result::Ok(())
+        self.term.call_external(command)
     }
 }
 
@@ -98,9 +175,17 @@ pub fn echo(ctx: &mut Context, args: &[&str]) -> CommandResult {
     Ok(())
 }
 
+/// Next key is not executed but its binding is shown in status
+pub fn explain_key(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.explain = Some(Vec::new());
+    ctx.app.status = "Press a key to explain".to_owned();
+    Ok(())
+}
+
 pub fn map_action(ctx: &mut Context, args: &[&str]) -> CommandResult {
     let mut x = |mut actions: Vec<(String, &String)>| {
-        actions.sort_by(|(k, _), (k2, _)| k.to_lowercase().cmp(&k2.to_lowercase())); // TODO: sorting of 'g' and 'G' is unstable but that's a minor
+        actions.sort_by_key(|(k, _)| k.to_lowercase()); // TODO: sorting of 'g' and 'G' is unstable but that's a minor
         let text = actions
             .into_iter()
             .map(|(keybind, action)| format!("{:10} {}", keybind, action))
@@ -134,23 +219,130 @@ pub fn unmap_action(ctx: &mut Context, args: &[&str]) -> CommandResult {
     Ok(())
 }
 
+/// Shows every binding with its origin: default, changed or added by :map, or unmapped
+pub fn list_maps(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    let text = maps_text(&ctx.parser.get_actions(), &ctx.app.default_maps);
+    ctx.term.text_via_less(text.as_str());
+    Ok(())
+}
+
+fn maps_text(current: &[(String, &String)], defaults: &BTreeMap<String, String>) -> String {
+    let mut rows: Vec<(&str, &str, &str)> = current
+        .iter()
+        .map(|(binding, action)| {
+            let origin = match defaults.get(binding) {
+                Some(default) if default == *action => "default",
+                Some(_) => "map (overrides default)",
+                None => "map",
+            };
+            (binding.as_str(), action.as_str(), origin)
+        })
+        .collect();
+    let unmapped = defaults
+        .iter()
+        .filter(|(binding, _)| !current.iter().any(|(b, _)| b == *binding))
+        .map(|(binding, action)| (binding.as_str(), action.as_str(), "unmapped default"));
+    rows.extend(unmapped);
+    rows.sort_by_key(|(binding, _, _)| binding.to_lowercase());
+    rows.into_iter()
+        .map(|(binding, action, origin)| format!("{:10} {:40} {}", binding, action, origin))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Reloads log with given revision range, HEAD when there's none.
+/// Errors of git (e.g. unknown revision) are reported once loading fails.
 pub fn reload(ctx: &mut Context, args: &[&str]) -> CommandResult {
-    ctx.app
-        .reload(Some(args.iter().map(|e| (*e).to_owned()).collect()));
+    let revision_range = crate::app::parse_revision_range(args)?;
+    ctx.app.reload(Some(revision_range));
     Ok(())
 }
 
+pub fn show(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 1);
+    let diff = ctx
+        .app
+        .diff(args[0])
+        .map_err(|e| format!("git show failed with: {e}"))?
+        .to_owned();
+    ctx.term.text_via_less(diff.as_str());
+    let encoding = ctx.app.encoding(args[0]).unwrap_or_default();
+    let notes = ctx.app.notes(args[0]).unwrap_or_default();
+    if let Some(status) = show_status(&encoding, &notes) {
+        ctx.app.status = status;
+    }
+    Ok(())
+}
+
+/// Returns status describing non default encoding and notes of shown commit
+fn show_status(encoding: &str, notes: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    if !encoding.is_empty() {
+        parts.push(format!("Encoding: {}", encoding));
+    }
+    if let Some(first) = notes.lines().next() {
+        let more = notes.lines().count() - 1;
+        parts.push(match more {
+            0 => format!("Notes: {}", first),
+            _ => format!("Notes: {} (+{} lines)", first, more),
+        });
+    }
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Shows current commit limited to given path, without path it's the same as show.
+pub fn review(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let sha = ctx.app.current_sha().ok_or_else(|| "No sha".to_owned())?;
+    match args {
+        [] => show(ctx, &[&sha]),
+        [path] => {
+            let diff = ctx
+                .app
+                .diff_for_path(&sha, path)
+                .map_err(|e| format!("git show failed with: {e}"))?;
+            ctx.term.text_via_less(diff.as_str());
+            Ok(())
+        }
+        _ => Err(format!("Expected at most 1 argument, got {}", args.len())),
+    }
+}
+
+/// Shows blame of path as of current commit
+pub fn blame(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 1);
+    let sha = ctx.app.current_sha().ok_or_else(|| "No sha".to_owned())?;
+    let blame = ctx
+        .app
+        .blame(&sha, args[0])
+        .map_err(|e| format!("git blame failed with: {e}"))?;
+    ctx.term.text_via_less(blame.as_str());
+    Ok(())
+}
+
+pub fn refresh_diff(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    match args.first().copied() {
+        None => {
+            let sha = ctx.app.current_sha().ok_or_else(|| "No sha".to_owned())?;
+            ctx.app.invalidate_diff_cache(Some(&sha));
+            ctx.app.status = format!("Diff cache cleared for {}", sha);
+        }
+        Some("all") => {
+            ctx.app.invalidate_diff_cache(None);
+            ctx.app.status = "Diff cache cleared".to_owned();
+        }
+        Some(arg) => return Err(format!("Unknown argument {}", arg)),
+    }
+    Ok(())
+}
+
+/// Prefills command line with reload of current revision range for editing
 pub fn enter_reload(ctx: &mut Context, args: &[&str]) -> CommandResult {
     AssertArgs!(args, 0);
-    let orig_rev_range = std::mem::take(&mut ctx.app.revision_range);
-    let revision_range = ["command".to_owned(), "reload".to_owned()]
-        .into_iter()
-        .chain(orig_rev_range)
-        .collect::<Vec<_>>();
-    let rv = revision_range.iter().map(|s| s as &str).collect::<Vec<_>>();
-    let result = set_mode(ctx, &rv);
-    ctx.app.revision_range = revision_range;
-    result
+    let range = shlex::join(ctx.app.revision_range.iter().map(String::as_str));
+    ctx.app
+        .mode_set(crate::app::Mode::Command(Some(format!("reload {}", range))));
+    Ok(())
 }
 
 pub fn quit(ctx: &mut Context, _args: &[&str]) -> CommandResult {
@@ -158,13 +350,52 @@ pub fn quit(ctx: &mut Context, _args: &[&str]) -> CommandResult {
     Ok(())
 }
 
+/// Clears message pinned by failed destructive command, does nothing without one
+pub fn dismiss(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    ctx.app.sticky = None;
+    Ok(())
+}
+
 pub fn up(ctx: &mut Context, _args: &[&str]) -> CommandResult {
-    ctx.app.prev(1);
+    let step = ctx.app.scroll_accel.step("up", ctx.app.clock.now());
+    ctx.app.prev(step);
     Ok(())
 }
 
 pub fn down(ctx: &mut Context, _args: &[&str]) -> CommandResult {
-    ctx.app.next(1);
+    let step = ctx.app.scroll_accel.step("down", ctx.app.clock.now());
+    ctx.app.next(step);
+    Ok(())
+}
+
+/// Columns scrolled horizontally when no count is given
+const SCROLL_COLUMNS: usize = 8;
+
+fn parse_columns(args: &[&str]) -> Result<usize, String> {
+    match args {
+        [] => Ok(SCROLL_COLUMNS),
+        [columns] => columns
+            .parse()
+            .map_err(|e| format!("Expected columns: {e}")),
+        _ => Err(format!("Expected at most 1 argument, got {}", args.len())),
+    }
+}
+
+pub fn scroll_left(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let columns = parse_columns(args)?;
+    ctx.app.scroll_left(columns);
+    Ok(())
+}
+
+pub fn scroll_right(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let columns = parse_columns(args)?;
+    ctx.app.scroll_right(columns);
+    Ok(())
+}
+
+pub fn scroll_home(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.h_offset = 0;
     Ok(())
 }
 
@@ -189,30 +420,240 @@ pub fn bottom(ctx: &mut Context, _args: &[&str]) -> CommandResult {
 }
 
 pub fn node_up(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    ctx.app.require_full("nodeup")?;
     ctx.app.prev_node();
     Ok(())
 }
 
 pub fn node_down(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    ctx.app.require_full("nodedown")?;
     ctx.app.next_node();
     Ok(())
 }
 
-pub fn node_center(ctx: &mut Context, _args: &[&str]) -> CommandResult {
-    ctx.app.center_node();
+pub fn goto_head(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app
+        .goto_head()
+        .ok_or_else(|| "HEAD is not in loaded range".to_owned())
+}
+
+pub fn goto_child(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.require_full("goto_child")?;
+    ctx.app
+        .goto_child()
+        .ok_or_else(|| "No child of this commit is loaded".to_owned())
+}
+
+/// Goes to line N, or N lines down/up with +N/-N
+pub fn goto_line(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 1);
+    let (line, clamped) = ctx.app.goto_line(args[0])?;
+    ctx.app.status = format!("Line {}/{}", line, ctx.app.log.len());
+    if clamped {
+        ctx.app.status.push_str(" (out of list)");
+    }
+    Ok(())
+}
+
+pub fn mark(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 1);
+    let register = parse_register(args[0])?;
+    ctx.app
+        .set_mark(register)
+        .ok_or_else(|| "No commit to mark".to_owned())?;
+    ctx.app.status = format!("Marked '{}", register);
     Ok(())
 }
 
+pub fn goto_mark(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 1);
+    let register = parse_register(args[0])?;
+    ctx.app.goto_mark(register)
+}
+
+pub fn tag_up(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    ctx.app
+        .prev_tag()
+        .ok_or_else(|| "No more tags above".to_owned())
+}
+
+pub fn tag_down(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    ctx.app
+        .next_tag()
+        .ok_or_else(|| "No more tags below".to_owned())
+}
+
+pub fn merge_up(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    ctx.app.require_full("mergeup")?;
+    ctx.app
+        .prev_merge()
+        .ok_or_else(|| "No more merges above".to_owned())
+}
+
+pub fn merge_down(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    ctx.app.require_full("mergedown")?;
+    ctx.app
+        .next_merge()
+        .ok_or_else(|| "No more merges below".to_owned())
+}
+
+pub fn node_center(ctx: &mut Context, _args: &[&str]) -> CommandResult {
+    ctx.app
+        .center_node()
+        .ok_or_else(|| "center is not available while long lines are wrapped".to_owned())
+}
+
 pub fn yank(ctx: &mut Context, args: &[&str]) -> CommandResult {
     AssertArgs!(args, 1);
-    let result = ctx
-        .clipboard
-        .as_mut()
-        .ok_or_else(|| "No clipboard provider!".to_owned())?
-        .set_contents(args[0].to_owned())
-        .map_err(|e| format!("Clipboard error: {e}"));
-    ctx.app.status = format!("yanked: {}", args[0]);
-    result
+    ctx.yank_text(args[0].to_owned())
+}
+
+pub fn yank_ref_line(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let template = if args.is_empty() {
+        ctx.app.ref_template.clone()
+    } else {
+        args.join(" ")
+    };
+    let text = ctx
+        .app
+        .log
+        .current()
+        .filter(|e| !e.git.hash.is_empty())
+        .ok_or_else(|| "No sha".to_owned())?
+        .git
+        .format(&template);
+    ctx.yank_text(text)
+}
+
+/// Diffs larger than this are yanked only with "force", pasting them is rarely intended
+const MAX_YANK_DIFF: usize = 1024 * 1024;
+
+fn check_yank_size(len: usize, force: bool) -> CommandResult {
+    if len > MAX_YANK_DIFF && !force {
+        return Err(format!(
+            "Diff has {} bytes (limit {}), yank anyway with: yank_diff %0 force",
+            len, MAX_YANK_DIFF
+        ));
+    }
+    Ok(())
+}
+
+/// Yanks output of git show for given sha, large diffs need "force" as second argument
+pub fn yank_diff(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let (sha, force) = match args {
+        [sha] => (*sha, false),
+        [sha, "force"] => (*sha, true),
+        _ => return Err("Expected: yank_diff <sha> [force]".to_owned()),
+    };
+    let diff = ctx
+        .app
+        .diff(sha)
+        .map_err(|e| format!("git show failed with: {e}"))?
+        .to_owned();
+    check_yank_size(diff.len(), force)?;
+    let (bytes, lines) = (diff.len(), diff.lines().count());
+    ctx.yank_text(diff)?;
+    ctx.app.status = format!("yanked diff ({} lines, {} bytes)", lines, bytes);
+    Ok(())
+}
+
+pub fn yank_message(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    let sha = ctx.app.current_sha().ok_or_else(|| "No sha".to_owned())?;
+    let message = ctx
+        .app
+        .message(&sha)
+        .map_err(|e| format!("Could not get message: {e}"))?;
+    let lines = message.lines().count();
+    ctx.yank_text(message)?;
+    ctx.app.status = format!("yanked message ({} lines)", lines);
+    Ok(())
+}
+
+pub fn yank_repo(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    let path = ctx.app.resolve_repository_path()?;
+    ctx.yank_text(path)
+}
+
+/// Yanks hashes of all selected commits in order of selection,
+/// separated with space (default) or newline
+pub fn yank_selected(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let separator = match args {
+        [] | ["space"] => " ",
+        ["newline"] => "\n",
+        _ => return Err("Expected separator: space or newline".to_owned()),
+    };
+    let hashes: Vec<_> = ctx
+        .app
+        .log
+        .iter_selected()
+        .map(|e| e.git.hash.as_str())
+        .collect();
+    if hashes.is_empty() {
+        return Err("Nothing is selected".to_owned());
+    }
+    let count = hashes.len();
+    ctx.yank_text(hashes.join(separator))?;
+    ctx.app.status = format!("yanked {} shas", count);
+    Ok(())
+}
+
+/// Yanks URL comparing selection anchor with current commit, remote is origin by default
+pub fn yank_compare(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let remote = match args {
+        [] => "origin",
+        [remote] => remote,
+        _ => return Err("Expected at most one remote".to_owned()),
+    };
+    let url = ctx.app.compare_url(remote)?;
+    ctx.yank_text(url)
+}
+
+/// Yanks short hash of current commit as OSC 8 hyperlink to its web page (see commit_template),
+/// for pasting into terminals which render the links
+pub fn yank_hyperlink(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let remote = match args {
+        [] => "origin",
+        [remote] => remote,
+        _ => return Err("Expected at most one remote".to_owned()),
+    };
+    let sha = ctx.app.current_sha().ok_or_else(|| "No sha".to_owned())?;
+    let url = ctx.app.commit_url(remote, &sha)?;
+    let short = &sha[..std::cmp::min(8, sha.len())];
+    ctx.yank_text(crate::osc8::hyperlink(&url, short))?;
+    // status must not get the escape sequence
+    ctx.app.status = format!("yanked hyperlink: {}", url);
+    Ok(())
+}
+
+pub fn pwd(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.status = ctx.app.resolve_repository_path()?;
+    Ok(())
+}
+
+/// Shows git invocation used to load the log
+pub fn git_command(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.status = ctx.app.git_command_line();
+    Ok(())
+}
+
+/// Copies git invocation reproducing current view, see gitcmd
+pub fn yank_git_command(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    let command = ctx.app.git_command_line();
+    ctx.yank_text(command)
+}
+
+/// Shows what %0, %1 and %_1 resolve to
+pub fn shas(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.status = ctx.app.placeholder_values();
+    Ok(())
 }
 
 pub fn select(ctx: &mut Context, _args: &[&str]) -> CommandResult {
@@ -220,6 +661,76 @@ pub fn select(ctx: &mut Context, _args: &[&str]) -> CommandResult {
     Ok(())
 }
 
+pub fn toggle_full_hash(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.full_hash = !ctx.app.full_hash;
+    ctx.app.status = format!(
+        "Showing {} hashes",
+        if ctx.app.full_hash {
+            "full"
+        } else {
+            "abbreviated"
+        }
+    );
+    Ok(())
+}
+
+pub fn toggle_merges(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.log_options.no_merges = !ctx.app.log_options.no_merges;
+    ctx.app.reload(None);
+    Ok(())
+}
+
+pub fn toggle_first_parent(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.log_options.first_parent = !ctx.app.log_options.first_parent;
+    ctx.app.reload(None);
+    Ok(())
+}
+
+pub fn toggle_wrap(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.wrap_subjects = !ctx.app.wrap_subjects;
+    ctx.app.status = if ctx.app.wrap_subjects {
+        "Long lines wrapped, center and goto_placement are off"
+    } else {
+        "Long lines cut"
+    }
+    .to_owned();
+    Ok(())
+}
+
+pub fn reset_view(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.reset_view();
+    ctx.app.status = "View reset".to_owned();
+    Ok(())
+}
+
+pub fn toggle_group(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.require_full("toggle_group")?;
+    ctx.app.group_reached_by = !ctx.app.group_reached_by;
+    ctx.app.status = if ctx.app.group_reached_by {
+        "Grouped by reached_by".to_owned()
+    } else {
+        "Flat view".to_owned()
+    };
+    Ok(())
+}
+
+pub fn toggle_fold(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    ctx.app.require_full("toggle_fold")?;
+    ctx.app.status = if ctx.app.toggle_fold() {
+        "Merged branches folded".to_owned()
+    } else {
+        "Merged branches unfolded".to_owned()
+    };
+    Ok(())
+}
+
 pub fn set_mode(ctx: &mut Context, args: &[&str]) -> CommandResult {
     match args.first().copied() {
         Some("command") => {
@@ -231,28 +742,423 @@ pub fn set_mode(ctx: &mut Context, args: &[&str]) -> CommandResult {
             }));
             Ok(())
         }
+        Some("jump") => {
+            ctx.app.mode_set(crate::app::Mode::Jump);
+            Ok(())
+        }
         Some(mode) => Err(format!("Unknown mode {}", mode)),
         _ => Err("Mode parameter is required".to_owned()),
     }
 }
 
+pub fn feed_keys(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    ctx.app.feed_keys(&args.join(" "));
+    Ok(())
+}
+
+fn parse_register(arg: &str) -> Result<char, String> {
+    let mut chars = arg.chars();
+    match (chars.next(), chars.next()) {
+        (Some(register), None) if crate::macros::REGISTERS.contains(&register) => Ok(register),
+        _ => Err(format!("Invalid register: {}", arg)),
+    }
+}
+
+pub fn macro_record(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 1);
+    let register = parse_register(args[0])?;
+    ctx.app.macros.start_recording(register);
+    ctx.app.status = format!("recording @{}", register);
+    Ok(())
+}
+
+/// Requests replay, keys are dispatched by main loop once this command returns
+pub fn macro_play(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 1);
+    let register = parse_register(args[0])?;
+    ctx.app.macros.request_replay(register)
+}
+
 pub fn status(ctx: &mut Context, args: &[&str]) -> CommandResult {
     ctx.app.status = args.join(" ");
     Ok(())
 }
 
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "on" | "true" | "1" => Ok(true),
+        "off" | "false" | "0" => Ok(false),
+        _ => Err(format!("Expected on/off, got: {}", value)),
+    }
+}
+
+pub fn set_option(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 1);
+    let (name, value) = args[0]
+        .split_once('=')
+        .ok_or_else(|| "Expected option=value".to_owned())?;
+    match name {
+        "date" => {
+            if value.is_empty() {
+                ctx.app.log_options.date = None;
+            } else if git::is_valid_date_format(value) {
+                ctx.app.log_options.date = Some(value.to_owned());
+            } else {
+                return Err(format!("Unknown date format: {}", value));
+            }
+            ctx.app.reload(None);
+        }
+        "ref_template" => ctx.app.ref_template = value.to_owned(),
+        "compare_template" => ctx.app.compare_template = value.to_owned(),
+        "commit_template" => ctx.app.commit_template = value.to_owned(),
+        "shell_prefix" => ctx.app.shell_prefix = value.to_owned(),
+        "shell_reload_prefix" => ctx.app.shell_reload_prefix = value.to_owned(),
+        // empty disables it
+        "on_select" => ctx.app.on_select.command = (!value.is_empty()).then(|| value.to_owned()),
+        "gui_template" => {
+            crate::app::parse_template(value)?;
+            ctx.app.gui_template = value.to_owned();
+        }
+        "show_template" => {
+            crate::app::parse_template(value)?;
+            ctx.app.show_template = value.to_owned();
+        }
+        "confirm" => ctx.app.confirm_destructive = parse_bool(value)?,
+        "timeout" => {
+            let ms = value
+                .parse()
+                .map_err(|e| format!("Expected timeout in ms: {e}"))?;
+            ctx.app.key_timeout = std::time::Duration::from_millis(ms);
+        }
+        "selection_marker" => {
+            if value.is_empty() {
+                return Err("Selection marker can't be empty".to_owned());
+            }
+            ctx.app.selection_marker = value.to_owned();
+        }
+        "selection_hints" => ctx.app.selection_hints = parse_bool(value)?,
+        "full_hash" => ctx.app.full_hash = parse_bool(value)?,
+        "osc52" => ctx.app.osc52 = parse_bool(value)?,
+        "fold_case" => {
+            ctx.parser.fold_case(parse_bool(value)?);
+        }
+        "chunk_size" => {
+            let size: usize = value
+                .parse()
+                .map_err(|_| format!("Expected chunk size, got: {}", value))?;
+            if size == 0 {
+                return Err("Chunk size has to be positive".to_owned());
+            }
+            ctx.app.chunking.size = size;
+        }
+        "chunk_interval" => {
+            let ms = value
+                .parse()
+                .map_err(|_| format!("Expected chunk interval in ms, got: {}", value))?;
+            ctx.app.chunking.interval = std::cmp::max(
+                std::time::Duration::from_millis(ms),
+                crate::app::MIN_FLUSH_INTERVAL,
+            );
+        }
+        "age" => ctx.app.show_age = parse_bool(value)?,
+        "age_buckets" => ctx.app.age_buckets = crate::age::parse_buckets(value)?,
+        "initials" => ctx.app.show_initials = parse_bool(value)?,
+        "ref_colors" => ctx.app.ref_colors = parse_bool(value)?,
+        "goto_placement" => ctx.app.goto_placement = crate::app::parse_placement(value)?,
+        // on/off or own type=color list
+        "commit_types" => {
+            ctx.app.commit_types = match parse_bool(value) {
+                Ok(true) => Some(crate::conventional::parse_types(
+                    crate::conventional::DEFAULT_TYPES,
+                )?),
+                Ok(false) => None,
+                Err(_) => Some(crate::conventional::parse_types(value)?),
+            };
+        }
+        "graph_colors" => ctx.app.graph_colors = crate::graph::parse_graph_colors(value)?,
+        "graph_width" => {
+            let width: usize = value
+                .parse()
+                .map_err(|_| format!("Expected graph width in columns, got: {}", value))?;
+            // 0 shows whole graph
+            ctx.app.graph_width = (width > 0).then_some(width);
+        }
+        "reached_by" => {
+            ctx.app.require_full("reached_by")?;
+            ctx.app.show_reached_by = parse_bool(value)?;
+        }
+        "max_selections" => {
+            let max: usize = value
+                .parse()
+                .map_err(|_| format!("Expected number of selections, got: {}", value))?;
+            // 0 lifts the limit
+            ctx.app.log.set_max_selections((max > 0).then_some(max));
+        }
+        "fixed_cursor" => ctx.app.log.set_scroll_mode(if parse_bool(value)? {
+            scrollview::ScrollMode::FixedCursor
+        } else {
+            scrollview::ScrollMode::Offset
+        }),
+        "no_merges" => {
+            ctx.app.log_options.no_merges = parse_bool(value)?;
+            ctx.app.reload(None);
+        }
+        "first_parent" => {
+            ctx.app.log_options.first_parent = parse_bool(value)?;
+            ctx.app.reload(None);
+        }
+        "accel" => ctx.app.scroll_accel.enabled = parse_bool(value)?,
+        "accel_window" => {
+            let ms = value
+                .parse()
+                .map_err(|e| format!("Expected window in ms: {e}"))?;
+            ctx.app.scroll_accel.window = std::time::Duration::from_millis(ms);
+        }
+        "accel_max" => {
+            ctx.app.scroll_accel.max_step = value
+                .parse()
+                .map_err(|e| format!("Expected max step: {e}"))?;
+        }
+        "start_at" => {
+            use clap::ValueEnum;
+            ctx.app.start_at = crate::app::StartAt::from_str(value, true)?;
+        }
+        _ => return Err(format!("Unknown option: {}", name)),
+    }
+    Ok(())
+}
+
+/// Limits log to given pathspecs, no arguments show whole history again
+pub fn set_paths(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    ctx.app.log_options.paths = args.iter().map(|a| (*a).to_owned()).collect();
+    ctx.app.reload(None);
+    Ok(())
+}
+
+/// Lists only commits of authors matching pattern (as git-log --author), no pattern lists all.
+/// git applies -i of grep to author pattern too, so it's matched ignoring case while grep -i is
+/// in effect.
+pub fn author_filter(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    ctx.app.log_options.author = match args {
+        [] => None,
+        [pattern] => Some((*pattern).to_owned()),
+        _ => return Err("Expected: author_filter [pattern]".to_owned()),
+    };
+    ctx.app.reload(None);
+    Ok(())
+}
+
+/// Lists only commits with message matching patterns (as git-log --grep), options -i and
+/// --all-match go before patterns; no patterns lists all
+pub fn grep(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    set_grep(&mut ctx.app.log_options, args);
+    ctx.app.reload(None);
+    Ok(())
+}
+
+fn set_grep(options: &mut git::LogOptions, args: &[&str]) {
+    let (mut ignore_case, mut all_match) = (false, false);
+    let mut patterns = args;
+    while let Some((first, rest)) = patterns.split_first() {
+        match *first {
+            "-i" => ignore_case = true,
+            "--all-match" => all_match = true,
+            _ => break,
+        }
+        patterns = rest;
+    }
+    options.grep = patterns.iter().map(|p| (*p).to_owned()).collect();
+    // options alone don't filter anything
+    options.ignore_case = ignore_case && !patterns.is_empty();
+    options.all_match = all_match && !patterns.is_empty();
+}
+
 pub fn exec(ctx: &mut Context, args: &[&str]) -> CommandResult {
     ctx.call_in_shell(shlex::join(args.iter().copied()))
+        .map(|_| ())
         .map_err(|a| format!("exec failed with: {a}"))
 }
 
+/// As exec, log is reloaded afterwards as command is expected to change the repository
+pub fn exec_reload(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let result = exec(ctx, args);
+    ctx.app.reload(None);
+    result
+}
+
+/// Returns shell part of user command template, `exec` or `!` prefix is optional
+pub fn exec_template(template: &str) -> &str {
+    template
+        .strip_prefix('!')
+        .or_else(|| template.strip_prefix("exec "))
+        .unwrap_or(template)
+}
+
+/// Runs user defined shell template (registered via --command), placeholders are resolved on
+/// each run and arguments are appended to the template.
+pub fn templated_exec(ctx: &mut Context, template: &str, args: &[&str]) -> CommandResult {
+    let words = crate::app::parse_template(exec_template(template))?;
+    let mut command = words
+        .iter()
+        .filter_map(|w| ctx.app.resolve_placeholder(w))
+        .collect::<Result<Vec<_>, _>>()?;
+    command.extend(args.iter().map(|a| (*a).to_owned()));
+    ctx.call_in_shell(shlex::join(command.iter().map(String::as_str)))
+        .map(|_| ())
+        .map_err(|a| format!("exec failed with: {a}"))
+}
+
+/// Runs show_template (`:set show_template=...`) for current commit
+pub fn exec_show(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    let words = ctx.app.show_command()?;
+    ctx.call_in_shell(shlex::join(words.iter().map(String::as_str)))
+        .map(|_| ())
+        .map_err(|a| format!("exec failed with: {a}"))
+}
+
+/// Starts gui_template (`:set gui_template=...`, gitk by default) for current commit in
+/// background, glog stays usable meanwhile
+pub fn gui(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    let words = ctx.app.gui_command()?;
+    // tokio reaps the child once it exits
+    tokio::process::Command::new(&words[0])
+        .args(&words[1..])
+        .current_dir(ctx.app.repository_path())
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Could not start {}: {e}", words[0]))?;
+    ctx.app.status = format!("Started {}", words.join(" "));
+    Ok(())
+}
+
+/// Shows changes of working tree since given commit with `git diff <sha>`
+pub fn diff_worktree(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 1);
+    let status = ctx
+        .call_in_shell(shlex::join(["git", "diff", args[0]]))
+        .map_err(|e| format!("git diff failed with: {e}"))?;
+    if !status.success() {
+        return Err(format!("git diff failed ({status})"));
+    }
+    Ok(())
+}
+
+/// Diffs commit visited before the current one against the current one
+pub fn diff_prev_visited(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    let prev = ctx
+        .app
+        .prev_visited()
+        .ok_or_else(|| "No previously visited commit".to_owned())?
+        .to_owned();
+    let current = ctx.app.current_sha().ok_or_else(|| "No sha".to_owned())?;
+    match ctx.call_in_shell(shlex::join(["git", "diff", &prev, &current])) {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("git diff failed ({status})")),
+        Err(e) => Err(format!("git diff failed with: {e}")),
+    }
+}
+
+/// Overwrites path in working tree with its version from given commit, eg. `restore_file %0 src`
+pub fn restore_file(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 2);
+    let (sha, path) = (args[0], args[1]);
+    ctx.app
+        .restore_file(sha, path)
+        .map_err(|e| format!("git restore failed with: {e}"))?;
+    ctx.app.status = format!("Restored {} from {}", path, sha);
+    Ok(())
+}
+
+/// Amends message of current commit when it's the checked out HEAD, cursor stays on it
+pub fn reword_head(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    let entry = ctx
+        .app
+        .log
+        .current()
+        .filter(|e| !e.git.hash.is_empty())
+        .ok_or_else(|| "No sha".to_owned())?;
+    let is_head = entry.git.refs.as_ref().is_some_and(|r| r.head.is_some());
+    if !is_head {
+        return Err("Current commit is not HEAD, see reword".to_owned());
+    }
+    // --only so changes staged meanwhile are not folded into the commit
+    let result = ctx.call_in_shell("git commit --amend --only".to_owned());
+    // cursor follows amended commit, or stays when amend didn't happen
+    let sha = ctx.app.head_sha().ok().or_else(|| ctx.app.current_sha());
+    ctx.app.reload_at(None, sha);
+    match result {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("git commit --amend failed ({status})")),
+        Err(e) => Err(format!("git commit --amend failed with: {e}")),
+    }
+}
+
+/// Rewords current commit: amends when it's HEAD, otherwise starts interactive rebase with
+/// that commit already marked for reword.
+pub fn reword(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    AssertArgs!(args, 0);
+    let entry = ctx
+        .app
+        .log
+        .current()
+        .filter(|e| !e.git.hash.is_empty())
+        .ok_or_else(|| "No sha".to_owned())?;
+    let is_head = entry.git.refs.as_ref().is_some_and(|r| r.head.is_some());
+    let sha = entry.git.hash.clone();
+    let cmd = if is_head {
+        "git commit --amend --only".to_owned()
+    } else {
+        let abbrev = ctx
+            .app
+            .abbreviated_sha(&sha)
+            .map_err(|e| format!("reword failed with: {e}"))?;
+        reword_command(&sha, &abbrev, !ctx.app.has_parent(&sha))
+    };
+    let result = ctx.call_in_shell(cmd);
+    ctx.app.reload(None);
+    match result {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!(
+            "reword failed ({status}), check for dirty tree or conflicts"
+        )),
+        Err(e) => Err(format!("reword failed with: {e}")),
+    }
+}
+
+/// Returns command starting interactive rebase with commit of given abbreviated sha marked for
+/// reword. Only its own todo line is changed, rebase is not started when there's no such line.
+/// Merges are rebased too, so they are kept in history and merge commit itself can be reworded.
+fn reword_command(sha: &str, abbrev: &str, root: bool) -> String {
+    let editor = format!(
+        "f() {{ sed -i -e 's/^pick {a} /reword {a} /' -e 's/^merge -C {a} /merge -c {a} /' \"$1\" \
+         && grep -q -e '^reword {a} ' -e '^merge -c {a} ' \"$1\"; }}; f",
+        a = abbrev
+    );
+    let base = if root {
+        "--root".to_owned()
+    } else {
+        format!("{}^", sha)
+    };
+    format!(
+        "GIT_SEQUENCE_EDITOR={} git rebase -i --rebase-merges {}",
+        shlex::quote(&editor),
+        base
+    )
+}
+
 struct SearchItem {
     text: String,
     hash: String,
 }
 
 impl SkimItem for SearchItem {
-    fn text(&self) -> Cow<str> {
+    fn text(&self) -> Cow<'_, str> {
         Cow::Borrowed(&self.text)
     }
 
@@ -265,8 +1171,8 @@ impl SkimItem for SearchItem {
     }
 }
 
-impl From<Entry> for SearchItem {
-    fn from(e: Entry) -> Self {
+impl From<&Entry> for SearchItem {
+    fn from(e: &Entry) -> Self {
         let refs = if let Some(r) = &e.git.refs {
             r.heads
                 .iter()
@@ -287,26 +1193,32 @@ impl From<Entry> for SearchItem {
                 e.git.author.as_str(),
             ]
             .join(" "),
-            hash: e.git.hash,
+            hash: e.git.hash.clone(),
         }
     }
 }
 
-pub fn search(ctx: &mut Context, _args: &[&str]) -> CommandResult {
-    let (tx_item, rx_item) = unbounded::<Arc<dyn SkimItem>>();
-    ctx.app
-        .log
-        .iter_all()
-        .filter(|e| !e.git.hash.is_empty())
-        .map(|e| Into::<SearchItem>::into(e.clone()))
-        .for_each(|e| {
-            tx_item
-                .send(Arc::new(e))
-                .expect("Sending element to skim failed")
-        });
-    drop(tx_item); // so that skim could know when to stop waiting for more items.
+/// Sends entries to skim one by one, stops early once skim is closed (receiver dropped).
+/// Returns number of items sent.
+fn feed_search_items<'a>(
+    entries: impl Iterator<Item = &'a Entry>,
+    tx_item: SkimItemSender,
+) -> usize {
+    let mut sent = 0;
+    for entry in entries.filter(|e| !e.git.hash.is_empty()) {
+        if tx_item.send(Arc::new(SearchItem::from(entry))).is_err() {
+            break;
+        }
+        sent += 1;
+    }
+    sent
+}
 
+/// Picks commit with skim, arguments are its initial query
+pub fn search(ctx: &mut Context, args: &[&str]) -> CommandResult {
+    let query = args.join(" ");
     let options = SkimOptionsBuilder::default()
+        .query((!query.is_empty()).then_some(query.as_str()))
         // .multi(true)
         .preview(Some("")) // preview should be specified to enable preview window
         .no_clear(true)
@@ -314,25 +1226,194 @@ pub fn search(ctx: &mut Context, _args: &[&str]) -> CommandResult {
         .build()
         .unwrap();
 
-    ctx.term
-        .call(|| {
-            if let Some(result) = Skim::run_with(&options, Some(rx_item)) {
-                match result.final_event {
-                    Event::EvActAccept(_) => {
-                        // no multi supported so take one
-                        if let Some(e) = result.selected_items.first() {
-                            let z = (**e).as_any().downcast_ref::<SearchItem>();
-                            if let Some(z) = z {
-                                ctx.app.goto(z.hash.as_ref());
-                            }
-                        }
-                    }
-                    Event::EvActAbort => {}
-                    _ => debug!("Not matched event: {:?}", result.final_event),
-                };
+    let log = &ctx.app.log;
+    let term = &mut ctx.term;
+    // items are fed from another thread so picker shows up right away and fills in,
+    // feeding stops when skim closes and the thread is joined at the end of the scope
+    let selected = std::thread::scope(|scope| {
+        let (tx_item, rx_item) = unbounded::<Arc<dyn SkimItem>>();
+        scope.spawn(move || feed_search_items(log.iter_all(), tx_item));
+        term.call(|| {
+            let result = Skim::run_with(&options, Some(rx_item))?;
+            match result.final_event {
+                // no multi supported so take one
+                Event::EvActAccept(_) => result.selected_items.first().and_then(|e| {
+                    (**e)
+                        .as_any()
+                        .downcast_ref::<SearchItem>()
+                        .map(|item| item.hash.clone())
+                }),
+                Event::EvActAbort => None,
+                _ => {
+                    debug!("Not matched event: {:?}", result.final_event);
+                    None
+                }
             }
         })
-        .map_err(|e| format!("Error in call: {e}"))?;
+    })
+    .map_err(|e| format!("Error in call: {e}"))?;
 
+    if let Some(hash) = selected {
+        ctx.app.goto(&hash, ctx.app.goto_placement);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use skim::prelude::{bounded, unbounded};
+
+    use super::{
+        check_yank_size, feed_search_items, maps_text, reword_command, set_grep, show_status,
+        MAX_YANK_DIFF,
+    };
+    use crate::app::Entry;
+
+    fn entries(count: usize) -> Vec<Entry> {
+        (0..count)
+            .map(|i| {
+                Entry::new(git::LogEntry {
+                    hash: format!("{:040x}", i),
+                    subject: format!("subject {}", i),
+                    ..Default::default()
+                })
+            })
+            .chain(std::iter::once(Entry::new(git::LogEntry::default())))
+            .collect()
+    }
+
+    #[test]
+    fn search_items_are_fed_until_picker_closes() {
+        let entries = entries(100_000);
+        std::thread::scope(|scope| {
+            // bounded so feeder waits for the reader, skim reads everything right away
+            let (tx, rx) = bounded(16);
+            let feeder = scope.spawn(|| feed_search_items(entries.iter(), tx));
+            let first = rx.recv().unwrap();
+            assert!(first.text().starts_with("00000000 subject 0"));
+            drop(rx);
+            assert!(feeder.join().unwrap() <= 17);
+        });
+        let (tx, rx) = unbounded();
+        assert_eq!(feed_search_items(entries.iter(), tx), 100_000);
+        drop(rx);
+
+        // graph only entries are skipped
+        let (tx, rx) = unbounded();
+        assert_eq!(feed_search_items(entries.iter().rev().take(3), tx), 2);
+        assert_eq!(rx.iter().count(), 2);
+    }
+
+    #[test]
+    fn maps_origin() {
+        let defaults = [("j", "down"), ("k", "up"), ("q", "quit")]
+            .map(|(b, a)| (b.to_owned(), a.to_owned()))
+            .into();
+        let (down, top, quit) = ("down".to_owned(), "top".to_owned(), "exec ls".to_owned());
+        let current = [
+            ("j".to_owned(), &down),
+            ("q".to_owned(), &quit),
+            ("gt".to_owned(), &top),
+        ];
+        let text = maps_text(&current, &defaults);
+        let rows: Vec<_> = text
+            .lines()
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "gt top map",
+                "j down default",
+                "k up unmapped default",
+                "q exec ls map (overrides default)",
+            ]
+        );
+    }
+
+    #[test]
+    fn yank_size() {
+        assert!(check_yank_size(MAX_YANK_DIFF, false).is_ok());
+        assert!(check_yank_size(MAX_YANK_DIFF + 1, false).is_err());
+        assert!(check_yank_size(MAX_YANK_DIFF + 1, true).is_ok());
+    }
+
+    #[test]
+    fn grep_args() {
+        let mut options = git::LogOptions::default();
+        set_grep(&mut options, &["-i", "--all-match", "fix", "-i"]);
+        assert_eq!(options.grep, ["fix", "-i"]);
+        assert!(options.ignore_case && options.all_match);
+
+        set_grep(&mut options, &["parser"]);
+        assert_eq!(options.grep, ["parser"]);
+        assert!(!options.ignore_case && !options.all_match);
+
+        set_grep(&mut options, &["-i"]);
+        assert!(options.grep.is_empty());
+        assert!(!options.ignore_case);
+    }
+
+    #[test]
+    fn status_of_show() {
+        assert_eq!(show_status("", ""), None);
+        assert_eq!(
+            show_status("ISO-8859-1", "reviewed"),
+            Some("Encoding: ISO-8859-1, Notes: reviewed".to_owned())
+        );
+        assert_eq!(
+            show_status("", "line one\nline two\nline three"),
+            Some("Notes: line one (+2 lines)".to_owned())
+        );
+    }
+
+    #[test]
+    fn reword_marks_only_target() {
+        let repo = git::test_repo::TestRepo::new("reword");
+        let root = repo.commit("a", "a", "a");
+        repo.git(["checkout", "-q", "-b", "side"]);
+        let side = repo.commit("c", "c", "c");
+        repo.git(["checkout", "-q", "main"]);
+        repo.commit("b", "b", "b");
+        repo.git(["merge", "-q", "--no-edit", "side"]);
+        repo.commit("d", "d", "d");
+        let reword = |sha: &str, abbrev: &str, is_root| {
+            repo.command("bash")
+                .args(["-c", &reword_command(sha, abbrev, is_root)])
+                .env("GIT_EDITOR", "sed -i 1s/.*/reworded/")
+                .output()
+                .unwrap()
+                .status
+        };
+        let subjects = || repo.git(["log", "--format=%s", "--topo-order"]);
+        let merge_parents = || {
+            let merges = repo.git(["rev-list", "--merges", "HEAD"]);
+            assert_eq!(merges.lines().count(), 1, "{merges}");
+            [1, 2].map(|n| repo.git(["log", "-1", "--format=%s", &format!("{merges}^{n}")]))
+        };
+
+        let abbrev = repo.git(["rev-parse", "--short", &side]);
+        assert!(reword(&side, &abbrev, false).success());
+        let log = subjects();
+        assert_eq!(log.lines().filter(|s| *s == "reworded").count(), 1, "{log}");
+        assert!(
+            log.lines().any(|s| s == "b") && !log.lines().any(|s| s == "c"),
+            "{log}"
+        );
+        assert_eq!(merge_parents(), ["b", "reworded"]);
+
+        let merge = repo.git(["rev-list", "--merges", "HEAD"]);
+        let abbrev = repo.git(["rev-parse", "--short", &merge]);
+        assert!(reword(&merge, &abbrev, false).success());
+        assert_eq!(subjects().lines().nth(1), Some("reworded"));
+        assert_eq!(merge_parents(), ["b", "reworded"]);
+
+        let abbrev = repo.git(["rev-parse", "--short", &root]);
+        assert!(reword(&root, &abbrev, true).success());
+        assert_eq!(subjects().lines().last(), Some("reworded"));
+
+        // no todo line of such commit, rebase is not started
+        assert!(!reword(&root, "0000000", true).success());
+        assert!(!repo.path().join(".git/rebase-merge").exists());
+    }
+}
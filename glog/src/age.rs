@@ -0,0 +1,112 @@
+/// Commit age bucket shown as a colored badge, see Buckets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Age {
+    Today,
+    Week,
+    Month,
+    Older,
+}
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+
+/// Upper bounds (in seconds from now) of Today, Week and Month buckets, anything older is Older.
+/// Today is the last 24 hours by default rather than a calendar day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Buckets {
+    pub today: i64,
+    pub week: i64,
+    pub month: i64,
+}
+
+impl Default for Buckets {
+    fn default() -> Self {
+        Self {
+            today: DAY,
+            week: 7 * DAY,
+            month: 30 * DAY,
+        }
+    }
+}
+
+impl Buckets {
+    /// Returns bucket of timestamp, commits from the future (clock skew) count as Today
+    pub fn age(&self, timestamp: i64, now: i64) -> Age {
+        let age = now.saturating_sub(timestamp);
+        if age < self.today {
+            Age::Today
+        } else if age < self.week {
+            Age::Week
+        } else if age < self.month {
+            Age::Month
+        } else {
+            Age::Older
+        }
+    }
+}
+
+/// Parses duration like 12h, 3d or 2w
+fn parse_duration(value: &str) -> Result<i64, String> {
+    let error = || format!("Expected duration like 12h, 3d or 2w, got: {}", value);
+    let unit_start = value.char_indices().last().map_or(0, |(idx, _)| idx);
+    let (number, unit) = value.split_at(unit_start);
+    let unit = match unit {
+        "h" => HOUR,
+        "d" => DAY,
+        "w" => 7 * DAY,
+        _ => return Err(error()),
+    };
+    let number: i64 = number.parse().map_err(|_| error())?;
+    Ok(number.saturating_mul(unit))
+}
+
+/// Parses bounds of buckets as comma separated durations eg. "1d,1w,30d"
+pub fn parse_buckets(value: &str) -> Result<Buckets, String> {
+    let bounds = value
+        .split(',')
+        .map(|d| parse_duration(d.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match bounds[..] {
+        [today, week, month] if 0 < today && today <= week && week <= month => {
+            Ok(Buckets { today, week, month })
+        }
+        [_, _, _] => Err("Bucket bounds have to be positive and ascending".to_owned()),
+        _ => Err(format!("Expected 3 bucket bounds, got: {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_buckets, Age, Buckets, DAY, HOUR};
+
+    #[test]
+    fn buckets() {
+        let buckets = Buckets::default();
+        let now = 1_700_000_000;
+        assert_eq!(buckets.age(now - HOUR, now), Age::Today);
+        assert_eq!(buckets.age(now + HOUR, now), Age::Today);
+        assert_eq!(buckets.age(now - DAY, now), Age::Week);
+        assert_eq!(buckets.age(now - 8 * DAY, now), Age::Month);
+        assert_eq!(buckets.age(now - 365 * DAY, now), Age::Older);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_buckets("1d,1w,30d"), Ok(Buckets::default()));
+        assert_eq!(
+            parse_buckets("12h, 2d, 2w"),
+            Ok(Buckets {
+                today: 12 * HOUR,
+                week: 2 * DAY,
+                month: 14 * DAY,
+            })
+        );
+        assert!(parse_buckets("1d,1w").is_err());
+        assert!(parse_buckets("1w,1d,30d").is_err());
+        assert!(parse_buckets("0d,1d,30d").is_err());
+        assert!(parse_buckets("1x,1w,30d").is_err());
+        assert!(parse_buckets("d,1w,30d").is_err());
+        assert!(parse_buckets("").is_err());
+        assert!(parse_buckets("1ż,1w,30d").is_err());
+    }
+}
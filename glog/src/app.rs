@@ -1,13 +1,18 @@
-use std::{path::PathBuf, sync::Arc, time::Duration};
+use std::{cell::OnceCell, collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
 
-use log::info;
+use log::{info, warn};
 use ratatui::style::Style;
 use stopwatch::Stopwatch;
 use tokio::{pin, select, sync::mpsc, task::JoinHandle};
-use tokio_stream::StreamExt;
+use tokio_stream::{Stream, StreamExt};
 use tui_textarea::TextArea;
 
 use crate::{
+    accel::Acceleration,
+    clock::{Clock, SystemClock},
+    confirm::Confirm,
+    fold::FirstParentFold,
+    macros::Macros,
     stateful_list::{Selectable, StatefulList},
     utils::WarnOnErr,
 };
@@ -31,6 +36,10 @@ impl Entry {
     pub fn selected(&self) -> bool {
         self.selected
     }
+
+    pub fn has_tag(&self) -> bool {
+        self.git.refs.as_ref().is_some_and(|r| !r.tags.is_empty())
+    }
 }
 
 impl Selectable for Entry {
@@ -47,6 +56,157 @@ impl Selectable for Entry {
 pub enum Mode {
     Normal,
     Command(Option<String>),
+    /// Visible rows are labeled and next key press jumps to labeled row
+    Jump,
+}
+
+/// Labels for Mode::Jump assigned in order to visible rows
+const JUMP_LABELS: &str = "asdfghjklqwertyuiopzxcvbnm";
+
+/// Where cursor lands after first chunk of data is loaded
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum StartAt {
+    #[default]
+    Top,
+    /// oldest of loaded entries
+    Bottom,
+    /// entry pointed to by HEAD
+    Head,
+}
+
+/// Where goto leaves the target in view
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Placement {
+    /// just moves cursor, target lands at scroll offset edge when it was out of view
+    #[default]
+    Keep,
+    Center,
+    /// target gets the first row
+    Top,
+}
+
+pub fn parse_placement(value: &str) -> Result<Placement, String> {
+    match value {
+        "keep" => Ok(Placement::Keep),
+        "center" => Ok(Placement::Center),
+        "top" => Ok(Placement::Top),
+        _ => Err(format!("Expected keep, center or top, got: {}", value)),
+    }
+}
+
+/// Default count of entries sent to UI at once
+const CHUNK_SIZE: usize = 100;
+/// Chunks are not flushed more often than UI is rendered (~60 fps) to keep throughput high
+pub const MIN_FLUSH_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Limits of a chunk sent by loader, chunk is flushed once any of them is reached
+#[derive(Clone, Copy, Debug)]
+pub struct Chunking {
+    pub size: usize,
+    /// time since chunk started, so UI updates even when entries come slowly
+    pub interval: Duration,
+}
+
+impl Default for Chunking {
+    fn default() -> Self {
+        Self {
+            size: CHUNK_SIZE,
+            interval: MIN_FLUSH_INTERVAL,
+        }
+    }
+}
+
+/// Screen cells of author drawn in a row on last frame, used to hit-test mouse clicks
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorHit {
+    pub row: u16,
+    pub columns: std::ops::Range<u16>,
+    pub author: String,
+}
+
+/// Reads entries from source in chunks and sends them as events of given generation.
+/// First chunk is sent as exactly one of FirstData or Error (NoData when source is genuinely
+/// empty, GitLog when it failed), then Data chunks follow and either Done or Error ends it.
+async fn load_log<S>(
+    entries: S,
+    generation: usize,
+    sender: &mpsc::UnboundedSender<(usize, LoaderEvent)>,
+    mut last_sha: Option<String>,
+    timer: Stopwatch,
+    chunking: Chunking,
+    compact: bool,
+) where
+    S: Stream<Item = Result<git::LogEntry, std::io::Error>>,
+{
+    let send = |event| {
+        sender
+            .send((generation, event))
+            .warn_on_err("Reload: queue error.")
+    };
+    pin!(entries); // so it can be used in async loops
+
+    let mut first = true;
+    loop {
+        let mut data = Vec::new();
+        let mut error = None;
+        let mut done = false;
+        let deadline = tokio::time::Instant::now() + chunking.interval;
+        while data.len() < chunking.size {
+            let next = if data.is_empty() {
+                entries.next().await
+            } else {
+                // entries which are ready are always taken, only waiting is limited
+                match tokio::time::timeout_at(deadline, entries.next()).await {
+                    Ok(next) => next,
+                    Err(_) => break,
+                }
+            };
+            match next {
+                Some(Ok(mut entry)) => {
+                    if compact {
+                        entry.compact();
+                    }
+                    data.push(Entry::new(entry))
+                }
+                Some(Err(e)) => {
+                    error = Some(e);
+                    break;
+                }
+                None => {
+                    done = true;
+                    break;
+                }
+            }
+        }
+        let finished = error.is_some() || done;
+
+        if first {
+            first = false;
+            // first chunk is important because it's the first delay to user
+            if data.is_empty() {
+                send(LoaderEvent::Error(
+                    error.map_or(LoaderError::NoData, LoaderError::GitLog),
+                ));
+                return;
+            }
+            send(LoaderEvent::FirstData {
+                data,
+                duration: timer.elapsed(),
+                last_sha: last_sha.take(),
+            });
+        } else if !data.is_empty() {
+            send(LoaderEvent::Data(data));
+        }
+
+        if let Some(error) = error {
+            send(LoaderEvent::Error(LoaderError::GitLog(error)));
+            return;
+        }
+        if finished {
+            break;
+        }
+    }
+    send(LoaderEvent::Done(timer.elapsed()));
 }
 
 pub enum LoaderError {
@@ -62,28 +222,166 @@ enum LoaderEvent {
     Data(Vec<Entry>),
     Done(Duration),
     Error(LoaderError),
+    /// ahead/behind counts of HEAD against its upstream
+    Upstream(Option<(usize, usize)>),
+}
+
+impl LoaderEvent {
+    /// Short description for event trace, without entries
+    fn summary(&self) -> String {
+        match self {
+            LoaderEvent::FirstData { data, duration, .. } => {
+                format!("FirstData({} entries, {:?})", data.len(), duration)
+            }
+            LoaderEvent::Data(data) => format!("Data({} entries)", data.len()),
+            LoaderEvent::Done(duration) => format!("Done({:?})", duration),
+            LoaderEvent::Error(LoaderError::NoData) => "Error(NoData)".to_owned(),
+            LoaderEvent::Error(LoaderError::GitLog(e)) => format!("Error({})", e),
+            LoaderEvent::Upstream(ahead_behind) => format!("Upstream({:?})", ahead_behind),
+        }
+    }
 }
 
+/// How long shutdown waits for aborted tasks
+const SHUTDOWN_GRACE: Duration = Duration::from_millis(500);
+
+/// Count of visited commits remembered
+const MAX_VISITED: usize = 32;
+
+/// Counted actions run at most this many times, or once per loaded entry when there are more
+const MAX_REPEAT: usize = 1000;
+
 pub struct App<'a> {
     mode: Mode,
     pub should_quit: bool,
     pub log: StatefulList<Entry>,
 
     repository: PathBuf,
+    /// git top-level of repository, resolved on first use
+    toplevel: OnceCell<Result<String, String>>,
     pub revision_range: Vec<String>,
+    pub log_options: git::LogOptions,
+    /// used only when there's no previous position to restore
+    pub start_at: StartAt,
+    /// commit cursor jumps to once it's loaded, see sha_argument
+    pub start_sha: Option<String>,
+    /// recorded key macros, see macro_record and macro_play
+    pub macros: Macros,
+    /// step scaling for held up/down keys
+    pub scroll_accel: Acceleration,
+    /// merged side branches are hidden when set, see toggle_fold
+    fold: Option<FirstParentFold>,
+    /// ref pointed to by HEAD among loaded entries ("HEAD" when detached)
+    head_ref: Option<String>,
+    /// ahead/behind of HEAD against upstream, computed on reload
+    ahead_behind: Option<(usize, usize)>,
+    /// count of columns skipped when drawing list lines
+    pub h_offset: usize,
+    /// show full hashes instead of 8 chars prefix in the list
+    pub full_hash: bool,
+    /// copy with OSC 52 terminal sequence when there is no clipboard provider
+    pub osc52: bool,
+    /// show colored commit age badge at start of the line
+    pub show_age: bool,
+    pub age_buckets: crate::age::Buckets,
+    pub graph_colors: crate::graph::GraphColors,
+    /// columns of graph shown in the list, wider graphs are cut with `…`
+    pub graph_width: Option<usize>,
+    /// colors of Conventional Commits types in subjects, None keeps subjects plain
+    pub commit_types: Option<Vec<(String, ratatui::style::Color)>>,
+    /// show reached_by of each run of commits in a column in front of the graph
+    pub group_reached_by: bool,
+    /// long lines continue on following rows instead of being cut. View still scrolls by
+    /// entries (page and scroll offset count entries, not rows) and ui leaves out rows at the top
+    /// to keep cursor visible, so centering is not available then, see place.
+    pub wrap_subjects: bool,
+    /// query of search typed in command line, its matches are highlighted
+    pub current_query: Option<String>,
+    /// where search and mark jumps leave the target in view
+    pub goto_placement: Placement,
+    /// filled by ui on each frame, see author_at
+    pub author_hits: Vec<AuthorHit>,
+    /// show author initials colored by author at start of the line
+    pub show_initials: bool,
+    /// color branches and remotes by their names instead of one color for each kind
+    pub ref_colors: bool,
+    /// show command line ref via which each commit was reached after its hash
+    pub show_reached_by: bool,
+    /// glyph in front of the selected entry, other entries are padded to its width
+    pub selection_marker: String,
+    /// show arrows in title when selections are above/below the view
+    pub selection_hints: bool,
+    /// ask before running destructive commands
+    pub confirm_destructive: bool,
+    /// destructive command (name, args) waiting for confirmation
+    pub confirm: Confirm<(String, Vec<String>)>,
+    /// how loader splits entries into chunks sent to UI
+    pub chunking: Chunking,
+    /// commits cursor was on, most recent last, see diff_prev_visited
+    visited: Vec<String>,
+    /// command line prefix running rest of it in shell, see expand_shell_prefix
+    pub shell_prefix: String,
+    /// as shell_prefix, log is reloaded afterwards
+    pub shell_reload_prefix: String,
+    /// command run when cursor settles on another commit
+    pub on_select: crate::select_hook::SelectHook,
+    /// JSON lines trace of events, see --trace-events
+    pub trace: crate::trace::EventTrace,
+    /// loaded entries keep only what the list shows, see --compact
+    pub compact: bool,
+    /// demo mode moving cursor when idle, see --autoplay
+    pub autoplay: Option<crate::autoplay::Autoplay>,
+    /// lines executed from command mode
+    pub history: crate::history::History,
+    /// commits marked by user, keyed by register
+    pub marks: std::collections::BTreeMap<char, String>,
+    /// bindings as set up at startup (binding → command), see maps
+    pub default_maps: std::collections::BTreeMap<String, String>,
+    /// keys typed so far while waiting for a key to explain, see explain_key
+    pub explain: Option<Vec<crossterm::event::KeyEvent>>,
+    /// time after which pending keys (ambiguous binding or count) are resolved
+    pub key_timeout: Duration,
+    pub key_deadline: Option<std::time::Instant>,
+    /// time of key timeout, acceleration, autoplay and select hook
+    pub clock: Box<dyn Clock>,
+    /// count being typed, for display only
+    pub pending_count: Option<usize>,
 
     pub status: String,
+    /// message kept until dismissed, shown instead of status
+    pub sticky: Option<String>,
+    /// template used by yank_ref_line, see git::LogEntry::format
+    pub ref_template: String,
+    /// command line run by exec_show, placeholders as in command line (%0, %1, ..)
+    pub show_template: String,
+    /// graphical tool started by gui, placeholders as in show_template
+    pub gui_template: String,
+    /// URL of comparing anchor with current commit, see expand_compare_template
+    pub compare_template: String,
+    /// URL of current commit, placeholders as in compare_template
+    pub commit_template: String,
     pub textarea: TextArea<'a>,
 
-    log_receiver: mpsc::UnboundedReceiver<LoaderEvent>,
-    log_sender: mpsc::UnboundedSender<LoaderEvent>,
+    log_receiver: mpsc::UnboundedReceiver<(usize, LoaderEvent)>,
+    log_sender: mpsc::UnboundedSender<(usize, LoaderEvent)>,
 
+    // Concurrency model: each reload aborts previous task and spawns a new one which waits for
+    // reload_mutex, so at most one loader produces events at a time. Events are tagged with
+    // generation of reload and events from previous generations are dropped in process().
     reload_task: Option<JoinHandle<()>>,
     reload_mutex: Arc<tokio::sync::Mutex<()>>,
+    reload_generation: usize,
+
+    /// git-show output per sha, cleared on reload or explicitly via invalidate_diff_cache
+    diff_cache: HashMap<String, String>,
 }
 
 impl<'a> App<'a> {
-    pub fn new(repository: PathBuf, revision_range: Vec<String>) -> App<'a> {
+    pub fn new(
+        repository: PathBuf,
+        revision_range: Vec<String>,
+        log_options: git::LogOptions,
+    ) -> App<'a> {
         let mut textarea = TextArea::default();
         textarea.set_cursor_line_style(Style::default());
         let (log_sender, log_receiver) = mpsc::unbounded_channel();
@@ -92,36 +390,109 @@ impl<'a> App<'a> {
             should_quit: false,
             log: StatefulList::new(),
             repository,
+            toplevel: OnceCell::new(),
             revision_range,
+            log_options,
+            start_at: StartAt::default(),
+            start_sha: None,
+            macros: Macros::default(),
+            scroll_accel: Acceleration::default(),
+            fold: None,
+            head_ref: None,
+            ahead_behind: None,
+            h_offset: 0,
+            full_hash: false,
+            osc52: false,
+            show_age: false,
+            age_buckets: Default::default(),
+            show_reached_by: false,
+            show_initials: false,
+            ref_colors: false,
+            group_reached_by: false,
+            goto_placement: Placement::Keep,
+            current_query: None,
+            wrap_subjects: false,
+            author_hits: Vec::new(),
+            commit_types: None,
+            graph_width: None,
+            graph_colors: Default::default(),
+            selection_marker: "> ".to_owned(),
+            selection_hints: true,
+            confirm_destructive: true,
+            confirm: Confirm::default(),
+            chunking: Chunking::default(),
+            compact: false,
+            trace: Default::default(),
+            on_select: Default::default(),
+            shell_prefix: "!".to_owned(),
+            shell_reload_prefix: "!!".to_owned(),
+            visited: Vec::new(),
+            autoplay: None,
+            history: Default::default(),
+            marks: Default::default(),
+            default_maps: Default::default(),
+            explain: None,
+            key_timeout: Duration::from_millis(1000),
+            key_deadline: None,
+            clock: Box::new(SystemClock),
+            pending_count: None,
             status: String::new(),
+            sticky: None,
+            ref_template: r#"%h ("%s")"#.to_owned(),
+            show_template: "git show --stat --patch %0".to_owned(),
+            gui_template: "gitk %0".to_owned(),
+            compare_template: "%r/compare/%1...%0".to_owned(),
+            commit_template: "%r/commit/%0".to_owned(),
             textarea,
             log_receiver,
             log_sender,
             reload_task: None,
             reload_mutex: Arc::new(tokio::sync::Mutex::new(())),
+            reload_generation: 0,
+            diff_cache: HashMap::new(),
         }
     }
 
     // Run though 'select!' in main loop to get data processing running
     pub async fn process(&mut self) {
         select! {
-            Some(loader_event) = self.log_receiver.recv() => {
+            Some((generation, loader_event)) = self.log_receiver.recv() => {
+                self.trace.record("loader", || {
+                    format!("generation {}: {}", generation, loader_event.summary())
+                });
+                if generation != self.reload_generation {
+                    return;
+                }
                 match loader_event {
                     LoaderEvent::FirstData { data, duration, last_sha } => {
                         self.log.reset();
-                        self.log.push(data);
-                        if let Some(last_sha) = last_sha {
-                            self.goto(&last_sha);
+                        self.head_ref = None;
+                        if self.fold.is_some() {
+                            self.fold = Some(FirstParentFold::default());
                         }
+                        self.push_entries(data);
+                        let found = if let Some(last_sha) = last_sha {
+                            self.goto(&last_sha, Placement::Keep).is_some()
+                        } else if self.start_sha.is_some() {
+                            // may be found in later chunks, reported when loading is done
+                            self.goto_start_sha();
+                            true
+                        } else {
+                            self.go_start_at().is_some()
+                        };
                         self.status = format!(
                             "Loaded first {} elements. Took: {}.{}s - loading rest in background..",
                             self.log.len(),
                             duration.as_secs(),
                             (duration.as_millis() % 1000) / 100
                         );
+                        if !found {
+                            self.status.push_str(" Start position not found, staying at top.");
+                        }
                     },
                     LoaderEvent::Data(data) => {
-                        self.log.push(data);
+                        self.push_entries(data);
+                        self.goto_start_sha();
                     },
                     LoaderEvent::Done(duration) => {
                         // TODO: add 'LOADING as last displayed item'
@@ -132,6 +503,12 @@ impl<'a> App<'a> {
                             duration.as_secs(),
                             (duration.as_millis() % 1000) / 100
                         );
+                        if let Some(sha) = self.start_sha.take() {
+                            self.status.push_str(&format!(" Commit {} not found.", sha));
+                        }
+                    },
+                    LoaderEvent::Upstream(ahead_behind) => {
+                        self.ahead_behind = ahead_behind;
                     },
                     LoaderEvent::Error(LoaderError::NoData) => {
                         self.status = "No log data!".to_string();
@@ -146,94 +523,183 @@ impl<'a> App<'a> {
         }
     }
 
+    fn push_entries(&mut self, data: Vec<Entry>) {
+        if self.head_ref.is_none() {
+            self.head_ref = data
+                .iter()
+                .find_map(|e| e.git.refs.as_ref().and_then(|r| r.head.clone()));
+        }
+        let start = self.log.len();
+        self.log.push(data);
+        if let Some(fold) = &mut self.fold {
+            let visible = self
+                .log
+                .iter_all()
+                .enumerate()
+                .skip(start)
+                .filter(|(_, e)| fold.visible(&e.git))
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+            self.log.extend_filter(visible);
+        }
+    }
+
+    /// Folds or unfolds all merged side branches, returns true when folded.
+    /// Cursor stays on current commit or moves to the closest visible one above it.
+    pub fn toggle_fold(&mut self) -> bool {
+        if self.fold.take().is_some() {
+            self.log.set_filter(None);
+            return false;
+        }
+        let mut fold = FirstParentFold::default();
+        let visible = self
+            .log
+            .iter_all()
+            .enumerate()
+            .filter(|(_, e)| fold.visible(&e.git))
+            .map(|(i, _)| i)
+            .collect();
+        self.log.set_filter(Some(visible));
+        self.fold = Some(fold);
+        true
+    }
+
+    /// Scrolls back to first column, unfolds merged branches and centers current commit.
+    /// Loaded data is kept, so filters passed to git (author, grep) stay.
+    pub fn reset_view(&mut self) {
+        self.h_offset = 0;
+        if self.fold.take().is_some() {
+            self.log.set_filter(None);
+        }
+        if !self.wrap_subjects {
+            self.log.center();
+        }
+    }
+
+    /// Returns current branch with ahead/behind counts eg. "[main ↑1 ↓2]", or "[detached]"
+    fn head_summary(&self) -> Option<String> {
+        let head = self.head_ref.as_deref()?;
+        if head == "HEAD" {
+            return Some("[detached]".to_owned());
+        }
+        Some(match self.ahead_behind {
+            Some((0, 0)) | None => format!("[{}]", head),
+            Some((ahead, 0)) => format!("[{} ↑{}]", head, ahead),
+            Some((0, behind)) => format!("[{} ↓{}]", head, behind),
+            Some((ahead, behind)) => format!("[{} ↑{} ↓{}]", head, ahead, behind),
+        })
+    }
+
     pub fn title(&self) -> String {
         let mut title = self.repository_path();
+        if let Some(head) = self.head_summary() {
+            title.push(' ');
+            title.push_str(&head);
+        }
         if let Some(item) = self.log.current() {
             title.push_str(" - ");
-            title.push_str(&item.git.reached_by);
+            title.push_str(item.git.reached_by_short());
             title.push(' ');
         }
+        if self.log_options.no_merges {
+            title.push_str("[no merges] ");
+        }
+        if self.log_options.first_parent {
+            title.push_str("[first parent] ");
+        }
+        if let Some(author) = &self.log_options.author {
+            title.push_str(&format!("[author: {}] ", author));
+        }
+        if !self.log_options.grep.is_empty() {
+            let flags = [
+                (self.log_options.ignore_case, "-i"),
+                (self.log_options.all_match, "--all-match"),
+            ];
+            let args = flags
+                .into_iter()
+                .filter_map(|(set, flag)| set.then_some(flag))
+                .chain(self.log_options.grep.iter().map(String::as_str));
+            title.push_str(&format!("[grep: {}] ", shlex::join(args)));
+        }
+        if !self.log_options.paths.is_empty() {
+            title.push_str(&format!(
+                "-- {} ",
+                shlex::join(self.log_options.paths.iter().map(String::as_str))
+            ));
+        }
+        if self.fold.is_some() {
+            title.push_str("[folded] ");
+        }
+        if let Some(register) = self.macros.recording() {
+            title.push_str(&format!("recording @{} ", register));
+        }
+        if self.selection_hints {
+            match self.log.selections_outside_view() {
+                (true, true) => title.push_str(&format!("{}▲▼ ", self.selection_marker)),
+                (true, false) => title.push_str(&format!("{}▲ ", self.selection_marker)),
+                (false, true) => title.push_str(&format!("{}▼ ", self.selection_marker)),
+                (false, false) => {}
+            }
+        }
         title
     }
 
     // Triggers asynchronous reload of data
     pub fn reload(&mut self, revision_range: Option<Vec<String>>) {
+        let last_sha = self.current_sha();
+        self.reload_at(revision_range, last_sha);
+    }
+
+    /// Reloads with cursor landing on given sha, eg. when current commit was rewritten
+    pub fn reload_at(&mut self, revision_range: Option<Vec<String>>, last_sha: Option<String>) {
         if let Some(rev) = revision_range {
             self.revision_range = rev;
             info!("New arguments for log: {:?}", self.revision_range);
         }
 
-        let last_sha = self.current_sha();
+        self.ahead_behind = None;
+        self.visited.clear();
         self.log.reset();
+        self.invalidate_diff_cache(None);
         self.status = "Reloading data".to_owned();
 
         let repository = self.repository.clone();
         let revision_range = self.revision_range.clone();
+        let log_options = self.log_options.clone();
         let sender = self.log_sender.clone();
+        let chunking = self.chunking;
+        let compact = self.compact;
 
+        self.reload_generation += 1;
+        let generation = self.reload_generation;
         let reload_mutex = Arc::clone(&self.reload_mutex);
         let reload_future = async move {
-            // tokio::Mutex is taken to ensure that only one future runs at a time
-            let _lock = reload_mutex.lock();
+            // tokio::Mutex is taken to ensure that only one future runs at a time,
+            // guard is held until the task finishes or is aborted
+            let _lock = reload_mutex.lock_owned().await;
             let timer = Stopwatch::start_new();
 
-            let data_in_chunks = git::get_log_data(&repository, &revision_range).await;
-            if let Err(error) = data_in_chunks {
-                sender
-                    .send(LoaderEvent::Error(LoaderError::GitLog(error)))
-                    .warn_on_err("Reload: queue error.");
-                return;
-            }
-            let data_in_chunks = data_in_chunks.unwrap().map(Entry::new);
-
-            pin!(data_in_chunks); // so it can be used in async loops
-
-            // TODO: refactor
-            {
-                let mut data: Vec<Entry> = vec![];
-                for _ in 0..100 {
-                    if let Some(x) = data_in_chunks.next().await {
-                        data.push(x);
-                    } else {
-                        break;
-                    }
-                }
-
-                // first chunk is important because it's the first delay to user
-                if data.is_empty() {
-                    sender
-                        .send(LoaderEvent::Error(LoaderError::NoData))
-                        .warn_on_err("Reload: queue error.");
-                }
-                sender
-                    .send(LoaderEvent::FirstData {
-                        data,
-                        duration: timer.elapsed(),
-                        last_sha,
-                    })
-                    .warn_on_err("Reload: queue error.");
-            }
-
-            'data_processing: loop {
-                let mut data: Vec<Entry> = vec![];
-                for _ in 0..100 {
-                    if let Some(x) = data_in_chunks.next().await {
-                        data.push(x);
-                    } else {
-                        if data.is_empty() {
-                            break 'data_processing;
-                        }
-                        break;
+            let load = async {
+                match git::get_log_data(&repository, &revision_range, &log_options).await {
+                    Ok(entries) => {
+                        load_log(
+                            entries, generation, &sender, last_sha, timer, chunking, compact,
+                        )
+                        .await
                     }
+                    Err(error) => sender
+                        .send((generation, LoaderEvent::Error(LoaderError::GitLog(error))))
+                        .warn_on_err("Reload: queue error."),
                 }
+            };
+            // runs alongside so it doesn't delay first data
+            let upstream = async {
+                let ahead_behind = git::get_ahead_behind(&repository).await;
                 sender
-                    .send(LoaderEvent::Data(data))
+                    .send((generation, LoaderEvent::Upstream(ahead_behind)))
                     .warn_on_err("Reload: queue error.");
-            }
-
-            sender
-                .send(LoaderEvent::Done(timer.elapsed()))
-                .warn_on_err("Reload: queue error.");
+            };
+            tokio::join!(load, upstream);
         };
 
         if let Some(reload_task) = &self.reload_task {
@@ -242,15 +708,98 @@ impl<'a> App<'a> {
         self.reload_task = Some(tokio::spawn(reload_future));
     }
 
+    /// Aborts background tasks and waits up to SHUTDOWN_GRACE for them to stop, so git child
+    /// processes (killed on drop) don't outlive glog
+    pub async fn shutdown(&mut self) {
+        let Some(reload_task) = self.reload_task.take() else {
+            return;
+        };
+        reload_task.abort();
+        if tokio::time::timeout(SHUTDOWN_GRACE, reload_task)
+            .await
+            .is_err()
+        {
+            warn!("Reload task did not stop in {:?}", SHUTDOWN_GRACE);
+        }
+    }
+
     pub fn next(&mut self, count: usize) -> Option<()> {
         self.log.scroll_next(count);
         Some(())
     }
+
+    /// Sets time after which pending keys are resolved, None when nothing is pending
+    pub fn restart_key_timeout(&mut self, pending: bool) {
+        self.key_deadline = pending.then(|| self.clock.now() + self.key_timeout);
+    }
+
+    /// Returns whether pending keys should be resolved now, the timeout and pending count are
+    /// cleared then
+    pub fn take_key_timeout(&mut self) -> bool {
+        let due = self
+            .key_deadline
+            .is_some_and(|deadline| self.clock.now() >= deadline);
+        if due {
+            self.key_deadline = None;
+            self.pending_count = None;
+        }
+        due
+    }
+
+    /// Returns how many times an action typed with count runs, vim_key saturates long counts so
+    /// they are capped to keep UI responsive
+    pub fn repeat_count(&self, count: usize) -> usize {
+        std::cmp::min(count, std::cmp::max(self.log.len(), MAX_REPEAT))
+    }
+
+    /// Advances cursor when autoplay is due, only in normal mode
+    pub fn autoplay_tick(&mut self, now: std::time::Instant) {
+        let Some(autoplay) = &mut self.autoplay else {
+            return;
+        };
+        if !matches!(self.mode, Mode::Normal) || !autoplay.step(now) {
+            return;
+        }
+        let at_end = self.log.current_position() + 1 >= self.log.len();
+        if !at_end {
+            self.log.scroll_next(1);
+        } else if autoplay.wrap {
+            self.log.scroll_start();
+        }
+    }
+
     pub fn prev(&mut self, count: usize) -> Option<()> {
         self.log.scroll_prev(count);
         Some(())
     }
 
+    pub fn scroll_right(&mut self, columns: usize) {
+        self.h_offset = self.h_offset.saturating_add(columns);
+    }
+
+    pub fn scroll_left(&mut self, columns: usize) {
+        self.h_offset = self.h_offset.saturating_sub(columns);
+    }
+
+    /// Remembers current commit as visited unless it's the last visited one
+    pub fn note_visited(&mut self) {
+        let Some(sha) = self.current_sha() else {
+            return;
+        };
+        if self.visited.last() != Some(&sha) {
+            self.visited.push(sha);
+            if self.visited.len() > MAX_VISITED {
+                self.visited.remove(0);
+            }
+        }
+    }
+
+    /// Returns commit visited before the current one
+    pub fn prev_visited(&self) -> Option<&str> {
+        let len = self.visited.len();
+        self.visited.get(len.checked_sub(2)?).map(String::as_str)
+    }
+
     pub fn current_sha(&self) -> Option<String> {
         let item = self.log.current()?;
         if item.git.hash.is_empty() {
@@ -259,13 +808,25 @@ impl<'a> App<'a> {
             Some(item.git.hash.clone())
         }
     }
+    /// Returns git top-level of repository, falls back to path as given when it can't be resolved.
     pub fn repository_path(&self) -> String {
-        // TODO: resolve the repo path
-        self.repository
+        self.resolve_repository_path().unwrap_or_else(|_| {
+            self.repository
+                .clone()
+                .into_os_string()
+                .into_string()
+                .unwrap_or_else(|_| "<unknown>".to_string())
+        })
+    }
+
+    /// Returns git top-level of repository, result (also failure) is cached.
+    pub fn resolve_repository_path(&self) -> Result<String, String> {
+        self.toplevel
+            .get_or_init(|| {
+                git::get_toplevel(&self.repository)
+                    .map_err(|e| format!("Could not resolve repository path: {e}"))
+            })
             .clone()
-            .into_os_string()
-            .into_string()
-            .unwrap_or_else(|_| "<unknown>".to_string())
     }
 
     pub fn top(&mut self) {
@@ -276,13 +837,109 @@ impl<'a> App<'a> {
         self.log.scroll_end()
     }
 
+    /// Moves cursor according to start_at, returns None if target was not found
+    fn go_start_at(&mut self) -> Option<()> {
+        match self.start_at {
+            StartAt::Top => self.top(),
+            StartAt::Bottom => self.bottom(),
+            StartAt::Head => return self.goto_head(),
+        }
+        Some(())
+    }
+
+    /// Moves cursor to start_sha when it's loaded already, it's forgotten then
+    fn goto_start_sha(&mut self) {
+        let Some(sha) = self.start_sha.clone() else {
+            return;
+        };
+        if self.goto(&sha, self.goto_placement).is_some() {
+            self.start_sha = None;
+        }
+    }
+
+    /// Moves cursor to the checked-out commit, returns None if it's not loaded
+    pub fn goto_head(&mut self) -> Option<()> {
+        let pos = self
+            .log
+            .iter_all()
+            .position(|e| e.git.refs.as_ref().is_some_and(|r| r.head.is_some()))?;
+        self.log.scroll_to_position(pos);
+        Some(())
+    }
+
+    /// Marks current commit, returns None when there's no commit under cursor
+    pub fn set_mark(&mut self, register: char) -> Option<()> {
+        let sha = self.current_sha()?;
+        self.marks.insert(register, sha);
+        Some(())
+    }
+
+    pub fn goto_mark(&mut self, register: char) -> Result<(), String> {
+        let sha = self
+            .marks
+            .get(&register)
+            .ok_or_else(|| format!("Mark '{} is not set", register))?;
+        let pos = self
+            .log
+            .iter_all()
+            .position(|e| &e.git.hash == sha)
+            .ok_or_else(|| format!("Mark '{} is not in loaded range", register))?;
+        self.place(pos, self.goto_placement);
+        Ok(())
+    }
+
+    /// Moves cursor to 1-based line N or by N visible lines with +N/-N. Target out of the list
+    /// is clamped, returns reached line and whether it was clamped.
+    pub fn goto_line(&mut self, target: &str) -> Result<(usize, bool), String> {
+        let invalid = || format!("Expected line number, +N or -N, got: {}", target);
+        if self.log.len() == 0 {
+            return Err("No entries".to_owned());
+        }
+        let in_list = if let Some(count) = target.strip_prefix('+') {
+            let count: usize = count.parse().map_err(|_| invalid())?;
+            self.log.scroll_by(count as isize)
+        } else if let Some(count) = target.strip_prefix('-') {
+            let count: usize = count.parse().map_err(|_| invalid())?;
+            self.log.scroll_by(-(count as isize))
+        } else {
+            let line: usize = target.parse().map_err(|_| invalid())?;
+            let pos = line.clamp(1, self.log.len()) - 1;
+            self.log.scroll_to_position(pos);
+            line == pos + 1
+        };
+        Ok((self.log.current_position() + 1, !in_list))
+    }
+
+    /// Fails for features which need fields dropped by --compact
+    pub fn require_full(&self, feature: &str) -> Result<(), String> {
+        if self.compact {
+            return Err(format!("{} is not available with --compact", feature));
+        }
+        Ok(())
+    }
+
+    /// Moves cursor to a loaded child of current commit, the most recent one when there are more.
+    /// Returns None when no child is loaded.
+    pub fn goto_child(&mut self) -> Option<()> {
+        let current = self.log.current_position();
+        let hash = &self.log.current()?.git.hash;
+        // children are always listed above their parents
+        let pos = self
+            .log
+            .iter_all()
+            .take(current)
+            .position(|e| e.git.parents.contains(hash))?;
+        self.log.scroll_to_position(pos);
+        Some(())
+    }
+
     pub fn next_node(&mut self) -> Option<()> {
         let selected = self.log.current_position();
-        let reached_by = &self.log.current()?.git.reached_by;
+        let reached_by = self.log.current()?.git.reached_by_short();
         let next = self
             .log
             .iter_all()
-            .map(|v| &v.git.reached_by)
+            .map(|v| v.git.reached_by_short())
             .enumerate()
             .skip(selected + 1)
             .find(|v| !v.1.is_empty() && v.1.ne(reached_by))
@@ -300,14 +957,14 @@ impl<'a> App<'a> {
         let (selected, reached_by) = self
             .log
             .iter_all()
-            .map(|v| &v.git.reached_by)
+            .map(|v| v.git.reached_by_short())
             .enumerate()
             .take(selected)
             .rfind(|v| !v.1.is_empty())?;
         let prev = self
             .log
             .iter_all()
-            .map(|v| &v.git.reached_by)
+            .map(|v| v.git.reached_by_short())
             .enumerate()
             .take(selected)
             .rfind(|v| v.1.ne(reached_by))
@@ -316,20 +973,185 @@ impl<'a> App<'a> {
         Some(())
     }
 
+    pub fn next_tag(&mut self) -> Option<()> {
+        let next = self
+            .log
+            .iter_all()
+            .enumerate()
+            .skip(self.log.current_position() + 1)
+            .find(|(i, e)| e.has_tag() && self.log.is_visible(*i))?
+            .0;
+        self.log.scroll_to_position(next);
+        Some(())
+    }
+
+    pub fn prev_tag(&mut self) -> Option<()> {
+        let prev = self
+            .log
+            .iter_all()
+            .enumerate()
+            .take(self.log.current_position())
+            .rfind(|(i, e)| e.has_tag() && self.log.is_visible(*i))?
+            .0;
+        self.log.scroll_to_position(prev);
+        Some(())
+    }
+
+    pub fn next_merge(&mut self) -> Option<()> {
+        let next = self
+            .log
+            .iter_all()
+            .enumerate()
+            .skip(self.log.current_position() + 1)
+            .find(|(i, e)| e.git.is_merge() && self.log.is_visible(*i))?
+            .0;
+        self.log.scroll_to_position(next);
+        Some(())
+    }
+
+    pub fn prev_merge(&mut self) -> Option<()> {
+        let prev = self
+            .log
+            .iter_all()
+            .enumerate()
+            .take(self.log.current_position())
+            .rfind(|(i, e)| e.git.is_merge() && self.log.is_visible(*i))?
+            .0;
+        self.log.scroll_to_position(prev);
+        Some(())
+    }
+
+    /// Centers cursor in view, None when long lines are wrapped as view doesn't count rows
     pub fn center_node(&mut self) -> Option<()> {
+        if self.wrap_subjects {
+            return None;
+        }
         self.log.center();
         Some(())
     }
 
     // TODO: change return type to something that can be used to display status etc?
+    /// Toggles selection of current entry, change of anchor (first selection) is shown in status
     pub fn select(&mut self) -> Option<()> {
-        self.log.toggle_select_for_current()
+        let anchor = |app: &Self| app.log.selected0().map(|e| e.git.hash.clone());
+        let before = anchor(self);
+        let result = self.log.toggle_select_for_current();
+        if anchor(self) != before {
+            self.status = match self.log.selected0() {
+                Some(entry) => format!(
+                    "anchor: {} {}",
+                    &entry.git.hash[..std::cmp::min(8, entry.git.hash.len())],
+                    entry.git.subject
+                ),
+                None => "anchor cleared".to_owned(),
+            };
+        }
+        result
+    }
+
+    /// Returns git-show output for given sha, fetching it only if it's not cached yet.
+    pub fn diff(&mut self, sha: &str) -> Result<&str, std::io::Error> {
+        if !self.diff_cache.contains_key(sha) {
+            let diff = git::get_show(&self.repository, sha, &[])?;
+            self.diff_cache.insert(sha.to_owned(), diff);
+        }
+        Ok(self.diff_cache[sha].as_str())
+    }
+
+    /// Returns git-show output for given sha limited to path, it's not cached.
+    pub fn diff_for_path(&self, sha: &str, path: &str) -> Result<String, std::io::Error> {
+        git::get_show(&self.repository, sha, &[path])
+    }
+
+    /// Returns full commit message for given sha.
+    pub fn message(&self, sha: &str) -> Result<String, std::io::Error> {
+        git::get_message(&self.repository, sha)
+    }
+
+    /// Returns sha which HEAD points to
+    pub fn head_sha(&self) -> Result<String, std::io::Error> {
+        git::rev_parse(&self.repository, "HEAD")
+    }
+
+    /// Returns abbreviated sha as git writes it in rebase todo list
+    pub fn abbreviated_sha(&self, sha: &str) -> Result<String, std::io::Error> {
+        git::abbreviate(&self.repository, sha)
+    }
+
+    /// Returns whether given commit has a parent, parents of entries are dropped in compact mode
+    pub fn has_parent(&self, sha: &str) -> bool {
+        git::rev_parse(&self.repository, &format!("{}^", sha)).is_ok()
+    }
+
+    /// Returns blame of path as of given sha, it's not cached.
+    pub fn blame(&self, sha: &str, path: &str) -> Result<String, std::io::Error> {
+        git::get_blame(&self.repository, sha, path)
+    }
+
+    /// Overwrites path in working tree with its content in given sha
+    pub fn restore_file(&self, sha: &str, path: &str) -> Result<(), std::io::Error> {
+        git::restore_file(&self.repository, sha, path)
+    }
+
+    /// Returns encoding header of given sha, fetched from git in compact mode
+    pub fn encoding(&self, sha: &str) -> Result<String, std::io::Error> {
+        if self.compact {
+            return git::get_encoding(&self.repository, sha);
+        }
+        Ok(self
+            .log
+            .iter_all()
+            .find(|e| e.git.hash == sha)
+            .map(|e| e.git.encoding.clone())
+            .unwrap_or_default())
+    }
+
+    /// Returns notes of given sha, fetched from git in compact mode
+    pub fn notes(&self, sha: &str) -> Result<String, std::io::Error> {
+        if self.compact {
+            return git::get_notes(&self.repository, sha);
+        }
+        Ok(self
+            .log
+            .iter_all()
+            .find(|e| e.git.hash == sha)
+            .map(|e| e.git.notes.clone())
+            .unwrap_or_default())
+    }
+
+    /// Drops cached diff for given sha or whole cache if None, forcing a re-fetch on next view.
+    pub fn invalidate_diff_cache(&mut self, sha: Option<&str>) {
+        match sha {
+            Some(sha) => {
+                self.diff_cache.remove(sha);
+            }
+            None => self.diff_cache.clear(),
+        }
+    }
+
+    /// Returns label for given row of current view when in Mode::Jump
+    pub fn jump_label(&self, view_row: usize) -> Option<char> {
+        match self.mode {
+            Mode::Jump => JUMP_LABELS.chars().nth(view_row),
+            _ => None,
+        }
+    }
+
+    /// Moves cursor to visible row with given label
+    pub fn jump_to_label(&mut self, label: char) -> Option<()> {
+        let row = JUMP_LABELS.find(label)?;
+        let pos = self.log.view_row_index(row)?;
+        self.log.scroll_to_position(pos);
+        Some(())
     }
 
     pub fn mode_set(&mut self, mode: Mode) {
         match &mode {
-            Mode::Normal => {}
+            Mode::Normal | Mode::Jump => {}
             Mode::Command(cmd) => {
+                if !matches!(self.mode, Mode::Command(_)) {
+                    self.history.reset_cursor();
+                }
                 // Remove input for next search. Do not recreate `self.textarea` instance to keep undo history so that users can
                 // restore previous input easily.
                 self.textarea.move_cursor(tui_textarea::CursorMove::End);
@@ -341,19 +1163,1191 @@ impl<'a> App<'a> {
             }
         }
         self.mode = mode;
+        self.update_query();
+    }
+
+    /// Resolves placeholder argument of command line.
+    /// Returns None when argument should be dropped (optional placeholder without value).
+    pub fn resolve_placeholder(&self, arg: &str) -> Option<Result<String, String>> {
+        match arg {
+            "%0" => {
+                let v = self.current_sha().ok_or_else(|| "No sha".to_owned());
+                Some(v)
+            }
+            "%_1" => {
+                // if there's no selected0 this will be None and will be filtered
+                self.log.selected0().map(|e| Ok(e.git.hash.clone()))
+            }
+            "%1" => {
+                let v = self
+                    .log
+                    .selected0()
+                    .map(|e| e.git.hash.clone())
+                    .ok_or_else(|| "No selection".to_owned());
+                Some(v)
+            }
+            "%%" => Some(Ok("%".to_owned())),
+            _ => Some(Ok(arg.to_owned())),
+        }
+    }
+
+    /// Returns values of commit placeholders, `none` for those which don't resolve
+    pub fn placeholder_values(&self) -> String {
+        ["%0", "%1", "%_1"]
+            .iter()
+            .map(|p| match self.resolve_placeholder(p) {
+                Some(Ok(sha)) => format!("{}={}", p, sha),
+                Some(Err(_)) | None => format!("{}=none", p),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Returns invocation of git used to load the log, quoted so it can be run in a shell
+    pub fn git_command_line(&self) -> String {
+        let args = git::log_args(&self.revision_range, &self.log_options);
+        ["git", "-C", &self.repository_path()]
+            .into_iter()
+            .map(shell_quote)
+            .chain(args.iter().map(|a| shell_quote(a)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Splits show_template and resolves its placeholders
+    pub fn show_command(&self) -> Result<Vec<String>, String> {
+        self.template_command(&self.show_template)
+    }
+
+    /// Splits gui_template and resolves its placeholders
+    pub fn gui_command(&self) -> Result<Vec<String>, String> {
+        self.template_command(&self.gui_template)
+    }
+
+    fn template_command(&self, template: &str) -> Result<Vec<String>, String> {
+        let words = parse_template(template)?;
+        words
+            .iter()
+            .filter_map(|w| self.resolve_placeholder(w))
+            .collect()
+    }
+
+    /// Returns URL comparing selection anchor with current commit on web page of given remote
+    pub fn compare_url(&self, remote: &str) -> Result<String, String> {
+        let anchor = self
+            .log
+            .selected0()
+            .ok_or_else(|| "No anchor selected".to_owned())?;
+        let current = self.current_sha().ok_or_else(|| "No sha".to_owned())?;
+        let web = self.web_url(remote)?;
+        Ok(expand_compare_template(
+            &self.compare_template,
+            &web,
+            &anchor.git.hash,
+            &current,
+        ))
+    }
+
+    /// Returns URL of given commit on web page of given remote
+    pub fn commit_url(&self, remote: &str, sha: &str) -> Result<String, String> {
+        let web = self.web_url(remote)?;
+        Ok(expand_compare_template(
+            &self.commit_template,
+            &web,
+            "",
+            sha,
+        ))
+    }
+
+    fn web_url(&self, remote: &str) -> Result<String, String> {
+        let repository = PathBuf::from(self.repository_path());
+        let url = git::get_remote_url(&repository, remote)
+            .map_err(|e| format!("No remote {}: {}", remote, e))?;
+        git::web_url(&url).ok_or_else(|| format!("Remote {} has no web URL: {}", remote, url))
+    }
+
+    /// Returns how placeholders of current command line would be resolved, for preview only.
+    pub fn placeholder_preview(&self) -> String {
+        let line = self.textarea.lines().last().map_or("", |l| l.as_str());
+        let words = shlex::split(line)
+            .unwrap_or_else(|| line.split_whitespace().map(str::to_owned).collect());
+        words
+            .iter()
+            .filter(|w| w.starts_with('%') && w.as_str() != "%%")
+            .map(|w| {
+                let value = match self.resolve_placeholder(w) {
+                    Some(Ok(v)) if v == *w => "?".to_owned(),
+                    Some(Ok(v)) => v.chars().take(8).collect(),
+                    Some(Err(e)) => e,
+                    None => "<dropped>".to_owned(),
+                };
+                format!("{}={}", w, value)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Inserts text at cursor of command line, entering command mode if needed.
+    /// Unlike mode_set it does not replace current command line.
+    pub fn feed_keys(&mut self, text: &str) {
+        if !matches!(self.mode, Mode::Command(_)) {
+            self.mode_set(Mode::Command(None));
+        }
+        self.textarea.insert_str(text);
+        self.update_query();
+    }
+
+    /// Takes query of search being typed in command line, any other line or mode clears it
+    pub fn update_query(&mut self) {
+        let line = self.textarea.lines().last().map_or("", |l| l.as_str());
+        self.current_query = match self.mode {
+            Mode::Command(_) => search_query(line),
+            Mode::Normal | Mode::Jump => None,
+        };
     }
 
     pub fn mode(&self) -> &Mode {
         &self.mode
     }
 
-    pub fn goto(&mut self, hash: &str) -> Option<()> {
+    pub fn goto(&mut self, hash: &str, placement: Placement) -> Option<()> {
         let pos = self
             .log
             .iter_all()
             // TODO: fix this
             .position(|e| e.git.hash.starts_with(hash))?;
-        self.log.scroll_to_position(pos);
+        self.place(pos, placement);
         Some(())
     }
+
+    /// Moves cursor to pos, placement is ignored when long lines are wrapped
+    fn place(&mut self, pos: usize, placement: Placement) {
+        self.log.scroll_to_position(pos);
+        if self.wrap_subjects {
+            return;
+        }
+        match placement {
+            Placement::Keep => {}
+            Placement::Center => self.log.center(),
+            Placement::Top => self.log.align_top(),
+        }
+    }
+
+    /// Returns author drawn at given screen cell on last frame
+    pub fn author_at(&self, column: u16, row: u16) -> Option<&str> {
+        self.author_hits
+            .iter()
+            .find(|hit| hit.row == row && hit.columns.contains(&column))
+            .map(|hit| hit.author.as_str())
+    }
+}
+
+/// Returns git-log --author pattern matching exactly given name, the pattern is a basic
+/// regular expression so its special characters are escaped
+pub fn author_pattern(name: &str) -> String {
+    let mut pattern = String::from("^");
+    for c in name.chars() {
+        if "\\.[]*^$".contains(c) {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    // author is matched against "Name <email>"
+    pattern.push_str(" <");
+    pattern
+}
+
+/// Returns the only revision argument when it looks like full or abbreviated sha (7 to 40 hex
+/// digits), e.g. `glog d401a57`. Such commit is better shown among all refs than as a start of
+/// log, caller still checks that it names a commit.
+pub fn sha_argument(revision_range: &[String]) -> Option<&str> {
+    match revision_range {
+        [arg] if (7..=40).contains(&arg.len()) && arg.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            Some(arg)
+        }
+        _ => None,
+    }
+}
+
+/// Returns query of command line like ":search text", None for other or empty queries
+pub fn search_query(line: &str) -> Option<String> {
+    let query = line.strip_prefix(':')?.strip_prefix("search ")?.trim();
+    (!query.is_empty()).then(|| query.to_owned())
+}
+
+/// Splits command template as command line would be, fails on bad quoting or empty template
+pub fn parse_template(template: &str) -> Result<Vec<String>, String> {
+    match shlex::split(template) {
+        Some(words) if !words.is_empty() => Ok(words),
+        Some(_) => Err("Template is empty".to_owned()),
+        None => Err(format!("Failed to parse template: {}", template)),
+    }
+}
+
+/// Quotes argument for shell, control characters (like field separator of log format) are
+/// escaped with bash $'..' quoting so the line stays printable
+fn shell_quote(arg: &str) -> String {
+    if !arg.chars().any(char::is_control) {
+        return shlex::quote(arg).into_owned();
+    }
+    let mut quoted = String::from("$'");
+    for c in arg.chars() {
+        match c {
+            '\\' | '\'' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c if c.is_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Expands %r (web URL of remote), %1 (anchor), %0 (current commit) and %% in template
+pub fn expand_compare_template(template: &str, web: &str, from: &str, to: &str) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        let rest = chars.clone();
+        match chars.next() {
+            Some('r') => result.push_str(web),
+            Some('1') => result.push_str(from),
+            Some('0') => result.push_str(to),
+            Some('%') => result.push('%'),
+            _ => {
+                // unknown placeholder stays untouched
+                result.push('%');
+                chars = rest;
+            }
+        }
+    }
+    result
+}
+
+/// Turns editor content into a single command line, None when there's nothing but whitespace
+pub fn edited_command(content: &str) -> Option<String> {
+    let line = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!line.is_empty()).then_some(line)
+}
+
+/// Expands shell prefixes of command line: `<shell>cmd` becomes `exec cmd` and
+/// `<shell_reload>cmd` becomes `exec_reload cmd`. Empty prefix is disabled, longer one wins.
+pub fn expand_shell_prefix(line: &str, shell: &str, shell_reload: &str) -> String {
+    let mut prefixes = [(shell_reload, "exec_reload"), (shell, "exec")];
+    prefixes.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+    prefixes
+        .iter()
+        .filter(|(prefix, _)| !prefix.is_empty())
+        .find_map(|(prefix, command)| {
+            line.strip_prefix(prefix)
+                .map(|rest| format!("{} {}", command, rest))
+        })
+        .unwrap_or_else(|| line.to_owned())
+}
+
+/// Validates revision range given to reload, empty range means HEAD.
+/// Paths are not part of the range, they are set separately (see paths command).
+pub fn parse_revision_range(args: &[&str]) -> Result<Vec<String>, String> {
+    if args.iter().any(|a| a.trim().is_empty()) {
+        return Err("Empty revision in range".to_owned());
+    }
+    if args.contains(&"--") {
+        return Err("Revision range can't contain paths, use :paths instead".to_owned());
+    }
+    if args.is_empty() {
+        return Ok(vec!["HEAD".to_owned()]);
+    }
+    Ok(args.iter().map(|a| (*a).to_owned()).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        author_pattern, edited_command, expand_compare_template, expand_shell_prefix, load_log,
+        parse_placement, parse_revision_range, search_query, sha_argument, shell_quote, App,
+        AuthorHit, Chunking, Entry, LoaderError, LoaderEvent, Mode, Placement, CHUNK_SIZE,
+        MAX_REPEAT, MAX_VISITED,
+    };
+
+    fn app_with(entries: Vec<git::LogEntry>) -> App<'static> {
+        let mut app = App::new(
+            std::path::PathBuf::from("."),
+            Vec::new(),
+            Default::default(),
+        );
+        app.log.set_view_height(10);
+        app.log.reset();
+        app.log.push(entries.into_iter().map(Entry::new).collect());
+        app
+    }
+
+    #[test]
+    fn placeholder_values() {
+        let mut app = app_with(vec![commit("a", &[]), git::LogEntry::default()]);
+        assert_eq!(app.placeholder_values(), "%0=a %1=none %_1=none");
+        app.select();
+        app.goto_line("2").unwrap();
+        assert_eq!(app.placeholder_values(), "%0=none %1=a %_1=a");
+    }
+
+    #[test]
+    fn select_shows_anchor() {
+        let mut app = app_with(
+            ["d401a57c0ffee", "bbbbbbbbbb"]
+                .map(|h| git::LogEntry {
+                    subject: format!("subject {}", &h[..1]),
+                    ..commit(h, &[])
+                })
+                .to_vec(),
+        );
+        app.select();
+        assert_eq!(app.status, "anchor: d401a57c subject d");
+        // single selection by default so it moves
+        app.goto_line("2").unwrap();
+        app.select();
+        assert_eq!(app.status, "anchor: bbbbbbbb subject b");
+        app.select();
+        assert_eq!(app.status, "anchor cleared");
+
+        // second selection keeps the anchor
+        app.log.set_max_selections(None);
+        app.select();
+        app.status.clear();
+        app.goto_line("1").unwrap();
+        app.select();
+        assert_eq!(app.status, "");
+        app.select();
+        assert_eq!(app.status, "");
+        app.goto_line("2").unwrap();
+        app.select();
+        assert_eq!(app.status, "anchor cleared");
+    }
+
+    #[test]
+    fn visited() {
+        let mut app = app_with(["a", "b", "c"].map(|h| commit(h, &[])).to_vec());
+        app.note_visited();
+        assert_eq!(app.prev_visited(), None);
+        app.goto_line("3").unwrap();
+        app.note_visited();
+        app.note_visited();
+        assert_eq!(app.prev_visited(), Some("a"));
+        app.goto_line("2").unwrap();
+        app.note_visited();
+        assert_eq!(app.prev_visited(), Some("c"));
+
+        for _ in 0..MAX_VISITED {
+            app.goto_line("-1").unwrap();
+            app.note_visited();
+            app.goto_line("+1").unwrap();
+            app.note_visited();
+        }
+        assert_eq!(app.visited.len(), MAX_VISITED);
+        assert_eq!(app.prev_visited(), Some("a"));
+    }
+
+    #[test]
+    fn goto_line_is_clamped() {
+        let mut app = app_with(["a", "b", "c", "d"].map(|h| commit(h, &[])).to_vec());
+        assert_eq!(app.goto_line("3"), Ok((3, false)));
+        assert_eq!(app.current_sha().as_deref(), Some("c"));
+        assert_eq!(app.goto_line("+1"), Ok((4, false)));
+        assert_eq!(app.goto_line("+1"), Ok((4, true)));
+        assert_eq!(app.goto_line("-2"), Ok((2, false)));
+        assert_eq!(app.goto_line("-5"), Ok((1, true)));
+        assert_eq!(app.goto_line("100"), Ok((4, true)));
+        assert_eq!(app.goto_line("0"), Ok((1, true)));
+        assert!(app.goto_line("+-1").is_err());
+        assert!(app.goto_line("x").is_err());
+        assert!(app_with(Vec::new()).goto_line("1").is_err());
+
+        // relative moves count visible lines only
+        let mut app = app_with(vec![
+            commit("m", &["c", "f"]),
+            commit("f", &["b"]),
+            commit("c", &["b"]),
+            commit("b", &[]),
+        ]);
+        app.toggle_fold();
+        assert_eq!(app.goto_line("+1"), Ok((3, false)));
+        assert_eq!(app.current_sha().as_deref(), Some("c"));
+    }
+
+    fn commit(hash: &str, parents: &[&str]) -> git::LogEntry {
+        git::LogEntry {
+            hash: hash.to_owned(),
+            parents: parents.iter().map(|p| (*p).to_owned()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn repository_path_is_toplevel() {
+        let repo = git::test_repo::TestRepo::new("toplevel");
+        repo.commit("sub/file", "", "Add file");
+        let app = App::new(repo.path().join("sub"), Vec::new(), Default::default());
+        assert_eq!(
+            app.resolve_repository_path().map(std::path::PathBuf::from),
+            Ok(repo.path().canonicalize().unwrap())
+        );
+        assert_eq!(
+            app.repository_path(),
+            app.resolve_repository_path().unwrap()
+        );
+    }
+
+    #[test]
+    fn compare_template() {
+        let web = "https://github.com/P4Cu/glog";
+        assert_eq!(
+            expand_compare_template("%r/compare/%1...%0", web, "abc", "def"),
+            "https://github.com/P4Cu/glog/compare/abc...def"
+        );
+        assert_eq!(
+            expand_compare_template("%r/-/compare/%1..%0?x=100%%&y=%z%", web, "a", "b"),
+            "https://github.com/P4Cu/glog/-/compare/a..b?x=100%&y=%z%"
+        );
+        // values are not expanded again
+        assert_eq!(expand_compare_template("%1", web, "%0", "b"), "%0");
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(shell_quote("main..topic"), "main..topic");
+        assert_eq!(shell_quote("a b"), "\"a b\"");
+        assert_eq!(shell_quote("%H\x1f%s 'x'\\"), "$'%H\\x1f%s \\'x\\'\\\\'");
+
+        let app = app_with(Vec::new());
+        let line = app.git_command_line();
+        assert!(line.starts_with("git -C "));
+        assert!(line.contains(" log --graph "));
+        assert!(line.contains("\\x1f%aN\\x1f"));
+    }
+
+    #[test]
+    fn shell_prefix() {
+        assert_eq!(expand_shell_prefix("!ls -l", "!", "!!"), "exec ls -l");
+        assert_eq!(
+            expand_shell_prefix("!!git pull", "!", "!!"),
+            "exec_reload git pull"
+        );
+        assert_eq!(expand_shell_prefix("echo !x", "!", "!!"), "echo !x");
+        // configured prefixes
+        assert_eq!(expand_shell_prefix("$ls", "$", "!"), "exec ls");
+        assert_eq!(expand_shell_prefix("!ls", "$", "!"), "exec_reload ls");
+        assert_eq!(expand_shell_prefix("!!ls", "!", ""), "exec !ls");
+        assert_eq!(expand_shell_prefix("!ls", "", ""), "!ls");
+    }
+
+    #[test]
+    fn command_from_editor() {
+        assert_eq!(
+            edited_command("exec git log\n  --oneline \n\n"),
+            Some("exec git log --oneline".to_owned())
+        );
+        assert_eq!(edited_command(" \n\t\n"), None);
+        assert_eq!(edited_command(""), None);
+    }
+
+    #[test]
+    fn revision_range() {
+        assert_eq!(parse_revision_range(&[]), Ok(vec!["HEAD".to_owned()]));
+        assert_eq!(
+            parse_revision_range(&["main..topic", "--all"]),
+            Ok(vec!["main..topic".to_owned(), "--all".to_owned()])
+        );
+        assert!(parse_revision_range(&["main", " "]).is_err());
+        assert!(parse_revision_range(&["main", "--", "src"]).is_err());
+    }
+
+    #[test]
+    fn show_command() {
+        let mut app = app_with(vec![commit("abc", &[])]);
+        assert_eq!(
+            app.show_command(),
+            Ok(vec![
+                "git".to_owned(),
+                "show".to_owned(),
+                "--stat".to_owned(),
+                "--patch".to_owned(),
+                "abc".to_owned()
+            ])
+        );
+        app.show_template = "git show --color-moved '%0' %_1".to_owned();
+        assert_eq!(
+            app.show_command(),
+            Ok(vec![
+                "git".to_owned(),
+                "show".to_owned(),
+                "--color-moved".to_owned(),
+                "abc".to_owned()
+            ])
+        );
+        app.show_template = "git show 'unterminated".to_owned();
+        assert!(app.show_command().is_err());
+        app.show_template = " ".to_owned();
+        assert!(app.show_command().is_err());
+
+        assert_eq!(
+            app.gui_command(),
+            Ok(vec!["gitk".to_owned(), "abc".to_owned()])
+        );
+    }
+
+    #[test]
+    fn fold_keeps_cursor_visible() {
+        let mut app = app_with(vec![
+            commit("m", &["c", "f", "g"]),
+            commit("f", &["b"]),
+            commit("c", &["b"]),
+            commit("b", &["a"]),
+        ]);
+        app.log.scroll_to_position(1);
+        assert_eq!(app.current_sha().as_deref(), Some("f"));
+
+        assert!(app.toggle_fold());
+        assert!(app.title().contains("[folded]"));
+        assert_eq!(app.current_sha().as_deref(), Some("m"));
+        app.next(1);
+        assert_eq!(app.current_sha().as_deref(), Some("c"));
+
+        // data loaded later is folded too
+        app.push_entries(vec![
+            Entry::new(commit("g", &["a"])),
+            Entry::new(commit("a", &[])),
+        ]);
+        app.bottom();
+        assert_eq!(app.current_sha().as_deref(), Some("a"));
+        app.prev(1);
+        assert_eq!(app.current_sha().as_deref(), Some("b"));
+
+        assert!(!app.toggle_fold());
+        assert_eq!(app.current_sha().as_deref(), Some("b"));
+        app.next(1);
+        assert_eq!(app.current_sha().as_deref(), Some("g"));
+    }
+
+    #[test]
+    fn reset_view() {
+        let entries = (0..30).map(|i| commit(&format!("c{}", i), &[])).collect();
+        let mut app = app_with(entries);
+        app.log.scroll_to_position(20);
+        app.toggle_fold();
+        app.h_offset = 7;
+
+        app.reset_view();
+        assert_eq!(app.h_offset, 0);
+        assert!(!app.title().contains("[folded]"));
+        assert_eq!(app.current_sha().as_deref(), Some("c20"));
+        // cursor row in the view of 10 rows
+        assert_eq!(app.log.iter_view().0, 5);
+    }
+
+    #[test]
+    fn sha_arguments() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(sha_argument(&args(&["d401a57"])), Some("d401a57"));
+        assert_eq!(
+            sha_argument(&args(&["D401A57C0FFEE000000000000000000000000000"])),
+            Some("D401A57C0FFEE000000000000000000000000000")
+        );
+        assert_eq!(sha_argument(&args(&["cafe"])), None);
+        assert_eq!(sha_argument(&args(&["d401a57~3"])), None);
+        assert_eq!(sha_argument(&args(&["d401a57..HEAD"])), None);
+        assert_eq!(sha_argument(&args(&["d401a57", "main"])), None);
+        assert_eq!(sha_argument(&args(&["main"])), None);
+        assert_eq!(sha_argument(&[]), None);
+    }
+
+    #[tokio::test]
+    async fn start_sha_found_in_later_chunk() {
+        let mut app = app_with(Vec::new());
+        app.start_sha = Some("c".to_owned());
+        let sender = app.log_sender.clone();
+        let generation = app.reload_generation;
+        let chunk = |hashes: &[&str]| hashes.iter().map(|h| Entry::new(commit(h, &[]))).collect();
+        let events = [
+            LoaderEvent::FirstData {
+                data: chunk(&["a", "b"]),
+                duration: std::time::Duration::ZERO,
+                last_sha: None,
+            },
+            LoaderEvent::Data(chunk(&["c"])),
+            LoaderEvent::Done(std::time::Duration::ZERO),
+        ];
+        for event in events {
+            sender.send((generation, event)).unwrap();
+            app.process().await;
+        }
+        assert_eq!(app.current_sha().as_deref(), Some("c"));
+        assert!(!app.status.contains("not found"));
+
+        app.start_sha = Some("x".to_owned());
+        sender
+            .send((generation, LoaderEvent::Done(std::time::Duration::ZERO)))
+            .unwrap();
+        app.process().await;
+        assert!(app.status.ends_with("Commit x not found."));
+        assert_eq!(app.start_sha, None);
+    }
+
+    #[test]
+    fn head_summary() {
+        let mut app = app_with(vec![commit("a", &[])]);
+        assert_eq!(app.head_summary(), None);
+
+        let mut head = commit("b", &[]);
+        head.refs = git::RefNames::from("HEAD -> refs/heads/main");
+        app.push_entries(vec![Entry::new(head)]);
+        assert_eq!(app.head_summary().as_deref(), Some("[main]"));
+        assert!(app.title().contains("[main]"));
+        app.ahead_behind = Some((2, 0));
+        assert_eq!(app.head_summary().as_deref(), Some("[main ↑2]"));
+        app.ahead_behind = Some((1, 3));
+        assert_eq!(app.head_summary().as_deref(), Some("[main ↑1 ↓3]"));
+
+        let mut app = app_with(Vec::new());
+        let mut detached = commit("c", &[]);
+        detached.refs = git::RefNames::from("HEAD, refs/heads/other");
+        app.push_entries(vec![Entry::new(detached)]);
+        assert_eq!(app.head_summary().as_deref(), Some("[detached]"));
+    }
+
+    #[test]
+    fn grep_title() {
+        let mut app = app_with(vec![commit("a", &[])]);
+        app.log_options.grep = vec!["fix".to_owned(), "two words".to_owned()];
+        assert!(app.title().contains(r#"[grep: fix "two words"] "#));
+        app.log_options.ignore_case = true;
+        app.log_options.all_match = true;
+        assert!(app
+            .title()
+            .contains(r#"[grep: -i --all-match fix "two words"] "#));
+    }
+
+    #[test]
+    fn goto_placement() {
+        let hashes: Vec<_> = (0..40).map(|i| format!("{:040x}", i)).collect();
+        let mut app = app_with(hashes.iter().map(|h| commit(h, &[])).collect());
+        app.log.set_view_height(10);
+        let row = |app: &App| app.log.iter_view().0;
+
+        assert_eq!(app.goto(&hashes[25], Placement::Center), Some(()));
+        assert_eq!(app.current_sha().as_ref(), Some(&hashes[25]));
+        assert_eq!(row(&app), 5);
+        assert_eq!(app.goto(&hashes[30], Placement::Top), Some(()));
+        assert_eq!(row(&app), 0);
+        // keep only moves cursor within view
+        assert_eq!(app.goto(&hashes[33], Placement::Keep), Some(()));
+        assert_eq!(row(&app), 3);
+        assert_eq!(app.goto("f", Placement::Center), None);
+
+        // view doesn't count wrapped rows, so center acts as keep: target lands at offset edge
+        app.wrap_subjects = true;
+        assert_eq!(app.goto(&hashes[20], Placement::Center), Some(()));
+        assert_eq!(row(&app), 4);
+        assert_eq!(app.center_node(), None);
+
+        assert_eq!(parse_placement("center"), Ok(Placement::Center));
+        assert!(parse_placement("middle").is_err());
+    }
+
+    #[test]
+    fn goto_head() {
+        let mut head = commit("c", &[]);
+        head.refs = git::RefNames::from("HEAD -> refs/heads/main");
+        let mut branch = commit("b", &[]);
+        branch.refs = git::RefNames::from("refs/heads/other");
+        let mut app = app_with(vec![commit("a", &[]), branch, head, commit("d", &[])]);
+        assert_eq!(app.goto_head(), Some(()));
+        assert_eq!(app.current_sha().as_deref(), Some("c"));
+
+        let mut app = app_with(vec![commit("a", &[]), commit("b", &[])]);
+        app.log.scroll_to_position(1);
+        assert_eq!(app.goto_head(), None);
+        assert_eq!(app.current_sha().as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn marks() {
+        let mut app = app_with(vec![commit("a", &[]), commit("b", &[])]);
+        assert!(app.goto_mark('x').is_err());
+        app.log.scroll_to_position(1);
+        assert_eq!(app.set_mark('x'), Some(()));
+        app.top();
+        assert_eq!(app.goto_mark('x'), Ok(()));
+        assert_eq!(app.current_sha().as_deref(), Some("b"));
+
+        // mark restored from previous session may point outside of range
+        app.marks.insert('y', "gone".to_owned());
+        assert!(app.goto_mark('y').is_err());
+        assert_eq!(app.current_sha().as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn autoplay() {
+        let start = std::time::Instant::now();
+        let step = std::time::Duration::from_millis(100);
+        let mut app = app_with(vec![commit("a", &[]), commit("b", &[])]);
+        app.autoplay = Some(crate::autoplay::Autoplay::new(step, false, start));
+        app.autoplay_tick(start + step);
+        assert_eq!(app.current_sha().as_deref(), Some("b"));
+        app.autoplay_tick(start + 2 * step);
+        assert_eq!(app.current_sha().as_deref(), Some("b"));
+
+        app.autoplay.as_mut().unwrap().wrap = true;
+        app.autoplay_tick(start + 3 * step);
+        assert_eq!(app.current_sha().as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn key_timeout() {
+        let clock = crate::clock::FakeClock::new();
+        let mut app = app_with(vec![commit("a", &[]), commit("b", &[])]);
+        app.clock = Box::new(clock.clone());
+        let mut parser = vim_key::VimKeyParser::default();
+        parser
+            .enable_count(true)
+            .add_action("g", "a")
+            .add_action("gg", "b");
+
+        parser.feed("3g");
+        app.restart_key_timeout(parser.is_pending());
+        app.pending_count = parser.pending_count();
+        clock.advance(app.key_timeout - std::time::Duration::from_millis(1));
+        assert!(!app.take_key_timeout());
+        assert_eq!(app.pending_count, Some(3));
+
+        clock.advance(std::time::Duration::from_millis(1));
+        assert!(app.take_key_timeout());
+        assert_eq!(app.pending_count, None);
+        assert_eq!(
+            parser.resolve_timeout(),
+            vim_key::ParsedAction::Counted(3, "a")
+        );
+        assert!(!app.take_key_timeout());
+
+        // nothing pending, no timeout
+        app.restart_key_timeout(false);
+        clock.advance(app.key_timeout);
+        assert!(!app.take_key_timeout());
+    }
+
+    #[test]
+    fn counted_action_after_timeout() {
+        let clock = crate::clock::FakeClock::new();
+        let mut app = app_with(vec![commit("a", &[]), commit("b", &[])]);
+        app.clock = Box::new(clock.clone());
+        let mut parser = vim_key::VimKeyParser::default();
+        parser
+            .enable_count(true)
+            .add_action("g", "a")
+            .add_action("gg", "b");
+
+        parser.feed("99999999999999999999999g");
+        app.restart_key_timeout(parser.is_pending());
+        clock.advance(app.key_timeout);
+        assert!(app.take_key_timeout());
+        let vim_key::ParsedAction::Counted(count, "a") = parser.resolve_timeout() else {
+            panic!("expected counted action");
+        };
+        assert_eq!(count, usize::MAX);
+        assert_eq!(app.repeat_count(count), MAX_REPEAT);
+        assert_eq!(app.repeat_count(3), 3);
+
+        let entries = (0..MAX_REPEAT + 5).map(|i| commit(&i.to_string(), &[]));
+        app.log.push(entries.map(Entry::new).collect());
+        assert_eq!(app.repeat_count(count), app.log.len());
+    }
+
+    #[test]
+    fn accelerated_scroll() {
+        let clock = crate::clock::FakeClock::new();
+        let mut app = app_with(vec![commit("a", &[])]);
+        app.clock = Box::new(clock.clone());
+        app.scroll_accel.enabled = true;
+        let step = |app: &mut App, pause| {
+            clock.advance(std::time::Duration::from_millis(pause));
+            app.scroll_accel.step("down", app.clock.now())
+        };
+        assert_eq!(step(&mut app, 0), 1);
+        assert_eq!(step(&mut app, 50), 2);
+        assert_eq!(step(&mut app, 100), 4);
+        // pause longer than the window
+        assert_eq!(step(&mut app, 101), 1);
+    }
+
+    #[test]
+    fn goto_child() {
+        // b and c are both children of d, x is child of c merged into a
+        let mut app = app_with(vec![
+            commit("a", &["b", "x"]),
+            commit("x", &["c"]),
+            commit("b", &["d"]),
+            git::LogEntry::default(),
+            commit("c", &["d"]),
+            commit("d", &["e"]),
+            commit("e", &[]),
+        ]);
+        app.log.scroll_to_position(5);
+        assert_eq!(app.goto_child(), Some(()));
+        assert_eq!(app.current_sha().as_deref(), Some("b"));
+        assert_eq!(app.goto_child(), Some(()));
+        assert_eq!(app.current_sha().as_deref(), Some("a"));
+        assert_eq!(app.goto_child(), None);
+        assert_eq!(app.current_sha().as_deref(), Some("a"));
+
+        app.log.scroll_to_position(4);
+        assert_eq!(app.goto_child(), Some(()));
+        assert_eq!(app.current_sha().as_deref(), Some("x"));
+
+        // graph only row has no children
+        app.log.scroll_to_position(3);
+        assert_eq!(app.goto_child(), None);
+    }
+
+    #[test]
+    fn merge_navigation() {
+        let mut app = app_with(vec![
+            commit("a", &["b"]),
+            commit("b", &["c", "x"]),
+            // graph only row
+            git::LogEntry::default(),
+            commit("c", &["d"]),
+            commit("d", &["e", "y", "z"]),
+            commit("e", &[]),
+        ]);
+        assert_eq!(app.next_merge(), Some(()));
+        assert_eq!(app.current_sha().as_deref(), Some("b"));
+        assert_eq!(app.next_merge(), Some(()));
+        assert_eq!(app.current_sha().as_deref(), Some("d"));
+        // no more merges, cursor stays
+        assert_eq!(app.next_merge(), None);
+        assert_eq!(app.current_sha().as_deref(), Some("d"));
+
+        assert_eq!(app.prev_merge(), Some(()));
+        assert_eq!(app.current_sha().as_deref(), Some("b"));
+        assert_eq!(app.prev_merge(), None);
+        assert_eq!(app.current_sha().as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn tag_navigation() {
+        let tagged = |hash: &str, refs: &str| git::LogEntry {
+            refs: git::RefNames::from(refs),
+            ..commit(hash, &[])
+        };
+        let mut app = app_with(vec![
+            tagged("a", "HEAD -> refs/heads/main"),
+            commit("b", &[]),
+            tagged("c", "tag: refs/tags/v2"),
+            git::LogEntry::default(),
+            commit("d", &[]),
+            tagged("e", "refs/remotes/origin/main"),
+            tagged("f", "tag: refs/tags/v1, tag: refs/tags/v1.0"),
+            commit("g", &[]),
+        ]);
+        assert_eq!(app.next_tag(), Some(()));
+        assert_eq!(app.current_sha().as_deref(), Some("c"));
+        assert_eq!(app.next_tag(), Some(()));
+        assert_eq!(app.current_sha().as_deref(), Some("f"));
+        // no more tags, cursor stays
+        assert_eq!(app.next_tag(), None);
+        assert_eq!(app.current_sha().as_deref(), Some("f"));
+
+        app.bottom();
+        assert_eq!(app.prev_tag(), Some(()));
+        assert_eq!(app.current_sha().as_deref(), Some("f"));
+        assert_eq!(app.prev_tag(), Some(()));
+        assert_eq!(app.current_sha().as_deref(), Some("c"));
+        assert_eq!(app.prev_tag(), None);
+        assert_eq!(app.current_sha().as_deref(), Some("c"));
+    }
+
+    async fn loader_events(items: Vec<Result<git::LogEntry, std::io::Error>>) -> Vec<LoaderEvent> {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        load_log(
+            tokio_stream::iter(items),
+            7,
+            &sender,
+            None,
+            stopwatch::Stopwatch::start_new(),
+            Chunking::default(),
+            false,
+        )
+        .await;
+        drop(sender);
+        let mut events = Vec::new();
+        while let Some((generation, event)) = receiver.recv().await {
+            assert_eq!(generation, 7);
+            events.push(event);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn loader_first_chunk() {
+        let events = loader_events(Vec::new()).await;
+        assert!(matches!(
+            events.as_slice(),
+            [LoaderEvent::Error(LoaderError::NoData)]
+        ));
+
+        let events = loader_events(vec![Ok(commit("a", &[]))]).await;
+        assert!(matches!(
+            events.as_slice(),
+            [LoaderEvent::FirstData { data, .. }, LoaderEvent::Done(_)] if data.len() == 1
+        ));
+
+        let events = loader_events(vec![Err(std::io::Error::other("bad revision"))]).await;
+        assert!(matches!(
+            events.as_slice(),
+            [LoaderEvent::Error(LoaderError::GitLog(e))] if e.to_string() == "bad revision"
+        ));
+
+        let events = loader_events(vec![
+            Ok(commit("a", &[])),
+            Err(std::io::Error::other("failed later")),
+        ])
+        .await;
+        assert!(matches!(
+            events.as_slice(),
+            [
+                LoaderEvent::FirstData { .. },
+                LoaderEvent::Error(LoaderError::GitLog(_))
+            ]
+        ));
+    }
+
+    #[tokio::test]
+    async fn loader_chunks() {
+        let items = (0..CHUNK_SIZE * 2 + 1)
+            .map(|i| Ok(commit(&i.to_string(), &[])))
+            .collect();
+        let sizes: Vec<_> = loader_events(items)
+            .await
+            .iter()
+            .map(|event| match event {
+                LoaderEvent::FirstData { data, .. } | LoaderEvent::Data(data) => data.len(),
+                LoaderEvent::Done(_) => 0,
+                LoaderEvent::Error(_) | LoaderEvent::Upstream(_) => panic!("Unexpected event"),
+            })
+            .collect();
+        assert_eq!(sizes, [CHUNK_SIZE, CHUNK_SIZE, 1, 0]);
+    }
+
+    #[tokio::test]
+    async fn loader_flushes_slow_chunks() {
+        // second entry comes much later than flush interval, first one is not held back
+        let items = async_stream::stream! {
+            yield Ok(commit("a", &[]));
+            yield Ok(commit("b", &[]));
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            yield Ok(commit("c", &[]));
+        };
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let chunking = Chunking {
+            size: 2,
+            ..Default::default()
+        };
+        let loader = load_log(
+            items,
+            1,
+            &sender,
+            None,
+            stopwatch::Stopwatch::start_new(),
+            chunking,
+            false,
+        );
+        let sizes = async {
+            let mut sizes = Vec::new();
+            while let Some((_, event)) = receiver.recv().await {
+                match event {
+                    LoaderEvent::FirstData { data, .. } | LoaderEvent::Data(data) => {
+                        sizes.push(data.len())
+                    }
+                    LoaderEvent::Done(_) => break,
+                    _ => panic!("Unexpected event"),
+                }
+            }
+            sizes
+        };
+        let ((), sizes) = tokio::join!(loader, sizes);
+        assert_eq!(sizes, [2, 1]);
+
+        let items = async_stream::stream! {
+            yield Ok(commit("a", &[]));
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            yield Ok(commit("b", &[]));
+        };
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let loader = load_log(
+            items,
+            1,
+            &sender,
+            None,
+            stopwatch::Stopwatch::start_new(),
+            Chunking::default(),
+            false,
+        );
+        let first = async {
+            match receiver.recv().await {
+                Some((_, LoaderEvent::FirstData { data, .. })) => data.len(),
+                _ => panic!("Expected first data"),
+            }
+        };
+        let ((), first) = tokio::join!(loader, first);
+        assert_eq!(first, 1);
+    }
+
+    #[tokio::test]
+    async fn stale_generation_events_are_ignored() {
+        let mut app = app_with(Vec::new());
+        app.reload_generation = 2;
+        let data = |hash| vec![Entry::new(commit(hash, &[]))];
+        let events = [
+            (
+                2,
+                LoaderEvent::FirstData {
+                    data: data("new1"),
+                    duration: std::time::Duration::ZERO,
+                    last_sha: None,
+                },
+            ),
+            // queued by aborted loader before reload
+            (1, LoaderEvent::Data(data("old"))),
+            (1, LoaderEvent::Done(std::time::Duration::ZERO)),
+            (2, LoaderEvent::Data(data("new2"))),
+        ];
+        let count = events.len();
+        for event in events {
+            app.log_sender.send(event).unwrap();
+        }
+        for _ in 0..count {
+            app.process().await;
+        }
+        let hashes: Vec<_> = app.log.iter_all().map(|e| e.git.hash.as_str()).collect();
+        assert_eq!(hashes, ["new1", "new2"]);
+        assert!(app.status.starts_with("Loaded first 1 elements"));
+    }
+
+    #[tokio::test]
+    async fn shutdown_aborts_reload() {
+        let repo = git::test_repo::TestRepo::new("shutdown");
+        for i in 0..3 {
+            repo.commit("file", &i.to_string(), &format!("commit {i}"));
+        }
+        let mut app = App::new(
+            repo.path().to_owned(),
+            vec!["HEAD".to_owned()],
+            Default::default(),
+        );
+        app.reload(None);
+        let task = app.reload_task.as_ref().unwrap().abort_handle();
+        app.shutdown().await;
+        assert!(task.is_finished());
+        assert!(app.reload_task.is_none());
+        // nothing to do second time
+        app.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn rapid_reloads_do_not_interleave() {
+        let repo = git::test_repo::TestRepo::new("rapid-reloads");
+        let mut commits: Vec<_> = (0..5)
+            .map(|i| repo.commit("file", &i.to_string(), &format!("commit {i}")))
+            .collect();
+        commits.reverse();
+        let mut app = App::new(
+            repo.path().to_owned(),
+            vec!["HEAD".to_owned()],
+            Default::default(),
+        );
+        for i in 0..20 {
+            app.reload(None);
+            // let some of the loaders start before they get aborted
+            if i % 3 == 0 {
+                tokio::task::yield_now().await;
+            }
+        }
+        let last = app.reload_generation;
+
+        let mut generations = Vec::new();
+        let mut hashes = Vec::new();
+        loop {
+            let (generation, event) = app.log_receiver.recv().await.expect("Loader stopped");
+            generations.push(generation);
+            match event {
+                LoaderEvent::FirstData { data, .. } | LoaderEvent::Data(data)
+                    if generation == last =>
+                {
+                    hashes.extend(data.into_iter().map(|e| e.git.hash));
+                }
+                LoaderEvent::Done(_) if generation == last => break,
+                LoaderEvent::Error(_) if generation == last => panic!("Last reload failed"),
+                _ => {}
+            }
+        }
+        assert!(
+            generations.windows(2).all(|w| w[0] <= w[1]),
+            "Events of generations interleaved: {:?}",
+            generations
+        );
+        assert_eq!(generations.last(), Some(&last));
+        assert_eq!(hashes, commits);
+    }
+
+    #[test]
+    fn author_hit_test() {
+        let mut app = app_with(Vec::new());
+        app.author_hits = vec![
+            AuthorHit {
+                row: 1,
+                columns: 30..38,
+                author: "Jane Doe".to_owned(),
+            },
+            AuthorHit {
+                row: 2,
+                columns: 28..31,
+                author: "Bob".to_owned(),
+            },
+        ];
+        assert_eq!(app.author_at(30, 1), Some("Jane Doe"));
+        assert_eq!(app.author_at(37, 1), Some("Jane Doe"));
+        assert_eq!(app.author_at(38, 1), None);
+        assert_eq!(app.author_at(30, 2), Some("Bob"));
+        assert_eq!(app.author_at(30, 3), None);
+
+        assert_eq!(author_pattern("Jane Doe"), "^Jane Doe <");
+        assert_eq!(author_pattern("j.d. [bot]*"), r"^j\.d\. \[bot\]\* <");
+    }
+
+    #[test]
+    fn query_of_search_line() {
+        assert_eq!(
+            search_query(":search fix parser ").as_deref(),
+            Some("fix parser")
+        );
+        assert_eq!(search_query(":search "), None);
+        assert_eq!(search_query(":search"), None);
+        assert_eq!(search_query(":searchx y"), None);
+        assert_eq!(search_query(":echo search x"), None);
+
+        let mut app = app_with(Vec::new());
+        app.feed_keys("search fix");
+        assert_eq!(app.current_query.as_deref(), Some("fix"));
+        app.mode_set(Mode::Normal);
+        assert_eq!(app.current_query, None);
+    }
 }
@@ -0,0 +1,59 @@
+use std::time::{Duration, Instant};
+
+/// Advances cursor periodically while there's no input, for screencasts and kiosks.
+/// Steps are checked on input ticks so interval is effectively rounded up to the tick rate.
+#[derive(Debug)]
+pub struct Autoplay {
+    pub interval: Duration,
+    /// jump to the top after reaching the end instead of stopping there
+    pub wrap: bool,
+    /// last key input or step, whichever came later
+    last: Instant,
+}
+
+impl Autoplay {
+    pub fn new(interval: Duration, wrap: bool, now: Instant) -> Self {
+        Self {
+            interval,
+            wrap,
+            last: now,
+        }
+    }
+
+    /// Key input pauses autoplay until it's idle for interval again
+    pub fn input(&mut self, now: Instant) {
+        self.last = now;
+    }
+
+    /// Returns true when cursor should advance at given time
+    pub fn step(&mut self, now: Instant) -> bool {
+        if now.saturating_duration_since(self.last) < self.interval {
+            return false;
+        }
+        self.last = now;
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::Autoplay;
+
+    #[test]
+    fn steps_when_idle() {
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        let mut autoplay = Autoplay::new(Duration::from_millis(500), false, start);
+        assert!(!autoplay.step(ms(250)));
+        assert!(autoplay.step(ms(500)));
+        assert!(!autoplay.step(ms(750)));
+        assert!(autoplay.step(ms(1000)));
+
+        // input pauses it for a whole interval
+        autoplay.input(ms(1100));
+        assert!(!autoplay.step(ms(1500)));
+        assert!(autoplay.step(ms(1600)));
+    }
+}
@@ -0,0 +1,56 @@
+use std::time::Instant;
+
+/// Source of current time for time based features (key timeout, acceleration, autoplay, select
+/// hook) so tests can drive them with FakeClock
+pub trait Clock: std::fmt::Debug {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock which moves only when told to, clones share the time
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct FakeClock(std::rc::Rc<std::cell::Cell<Instant>>);
+
+#[cfg(test)]
+impl FakeClock {
+    pub fn new() -> Self {
+        Self(std::rc::Rc::new(std::cell::Cell::new(Instant::now())))
+    }
+
+    pub fn advance(&self, by: std::time::Duration) {
+        self.0.set(self.0.get() + by);
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.0.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{Clock, FakeClock};
+
+    #[test]
+    fn fake_clock_moves_when_advanced() {
+        let clock = FakeClock::new();
+        let shared = clock.clone();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+        shared.advance(Duration::from_millis(250));
+        assert_eq!(clock.now(), start + Duration::from_millis(250));
+    }
+}
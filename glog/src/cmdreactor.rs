@@ -4,26 +4,78 @@ use log::{debug, error};
 
 pub type CommandResult = Result<(), String>;
 pub type FnCommand<CONTEXT> = fn(&mut CONTEXT, &[&str]) -> CommandResult;
+/// Command parametrized by data given when registered, eg. user defined template
+pub type FnDataCommand<CONTEXT> = fn(&mut CONTEXT, &str, &[&str]) -> CommandResult;
+
+enum Callable<T> {
+    Fn(FnCommand<T>),
+    WithData(FnDataCommand<T>, String),
+}
+
+/// Command with its metadata
+struct CommandSpec<T> {
+    cmd: Callable<T>,
+    /// changes repository state so it should be confirmed before execution
+    destructive: bool,
+    /// runs commands expected to change repository, not confirmed but refused in read-only mode
+    writes: bool,
+}
+
+impl<T> From<FnCommand<T>> for CommandSpec<T> {
+    fn from(cmd: FnCommand<T>) -> Self {
+        CommandSpec {
+            cmd: Callable::Fn(cmd),
+            destructive: false,
+            writes: false,
+        }
+    }
+}
 
 pub struct CmdReactor<T> {
-    commands: HashMap<&'static str, FnCommand<T>>,
+    commands: HashMap<String, CommandSpec<T>>,
+    /// destructive and writing commands are refused
+    read_only: bool,
 }
 
 impl<T> CmdReactor<T> {
     pub fn new() -> Self {
         CmdReactor {
             commands: HashMap::new(),
+            read_only: false,
         }
     }
 
     #[allow(dead_code)]
     pub fn add_command(&mut self, name: &'static str, cmd: FnCommand<T>) {
-        match self.commands.entry(name) {
+        match self.commands.entry(name.to_owned()) {
             std::collections::hash_map::Entry::Occupied(_) => {
                 error!("Already contains command: {}", name)
             }
             std::collections::hash_map::Entry::Vacant(entry) => {
-                entry.insert(cmd);
+                entry.insert(cmd.into());
+            }
+        }
+    }
+
+    /// Adds command created at runtime which gets data on each call, fails when the name is
+    /// already taken
+    pub fn add_data_command(
+        &mut self,
+        name: &str,
+        cmd: FnDataCommand<T>,
+        data: String,
+    ) -> CommandResult {
+        match self.commands.entry(name.to_owned()) {
+            std::collections::hash_map::Entry::Occupied(_) => {
+                Err(format!("Command already exists: {}", name))
+            }
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(CommandSpec {
+                    cmd: Callable::WithData(cmd, data),
+                    destructive: false,
+                    writes: false,
+                });
+                Ok(())
             }
         }
     }
@@ -33,18 +85,53 @@ impl<T> CmdReactor<T> {
         I: IntoIterator<Item = (&'static str, FnCommand<T>)>,
     {
         // TODO: warning when overriding command
-        self.commands.extend(iter);
+        self.commands.extend(
+            iter.into_iter()
+                .map(|(name, cmd)| (name.to_owned(), cmd.into())),
+        );
+    }
+
+    pub fn set_destructive(&mut self, name: &str) {
+        match self.commands.get_mut(name) {
+            Some(spec) => spec.destructive = true,
+            None => error!("Cannot mark unknown command as destructive: {}", name),
+        }
+    }
+
+    pub fn is_destructive(&self, name: &str) -> bool {
+        self.commands.get(name).is_some_and(|spec| spec.destructive)
+    }
+
+    pub fn set_writes(&mut self, name: &str) {
+        match self.commands.get_mut(name) {
+            Some(spec) => spec.writes = true,
+            None => error!("Cannot mark unknown command as writing: {}", name),
+        }
+    }
+
+    pub fn set_read_only(&mut self, enabled: bool) {
+        self.read_only = enabled;
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
     }
 
     pub fn execute(&self, context: &mut T, command: &'_ str, args: Vec<String>) -> CommandResult {
         debug!("Executing: {} with args {:?}", command, args);
 
-        let cmd = self
+        let spec = self
             .commands
             .get(command)
             .ok_or(format!("Command not found: {command}"))?;
+        if self.read_only && (spec.destructive || spec.writes) {
+            return Err(format!("{command}: disabled in read-only mode"));
+        }
         let z: &Vec<&str> = &args.iter().map(|s| s as &str).collect();
-        cmd(context, z)
+        match &spec.cmd {
+            Callable::Fn(cmd) => cmd(context, z),
+            Callable::WithData(cmd, data) => cmd(context, data, z),
+        }
     }
 }
 
@@ -73,4 +160,92 @@ mod test {
         }
         assert_eq!(x, 300);
     }
+
+    #[test]
+    fn with_data() {
+        let mut x = 0;
+        let mut reactor = CmdReactor::<Context>::new();
+        reactor.add_command("add", |ctx, _args| {
+            *ctx.number += 1;
+            Ok(())
+        });
+        let add_parsed: super::FnDataCommand<Context> = |ctx, data, args| {
+            *ctx.number += data.parse::<i32>().unwrap() * args.len() as i32;
+            Ok(())
+        };
+        assert!(reactor
+            .add_data_command("add_ten", add_parsed, "10".to_owned())
+            .is_ok());
+        assert!(reactor
+            .add_data_command("add", add_parsed, "1".to_owned())
+            .is_err());
+        assert!(reactor
+            .add_data_command("add_ten", add_parsed, "1".to_owned())
+            .is_err());
+
+        let mut d = Context { number: &mut x };
+        {
+            assert!(reactor
+                .execute(&mut d, "add_ten", vec!["a".to_owned(), "b".to_owned()])
+                .is_ok());
+            assert!(reactor.execute(&mut d, "add", vec![]).is_ok());
+        }
+        assert_eq!(x, 21);
+    }
+
+    #[test]
+    fn destructive() {
+        let mut reactor = CmdReactor::<Context>::new();
+        reactor.add_commands([
+            ("safe", (|_, _| Ok(())) as super::FnCommand<Context>),
+            ("unsafe", |_, _| Ok(())),
+        ]);
+        reactor.set_destructive("unsafe");
+        reactor.set_destructive("unknown");
+        assert!(!reactor.is_destructive("safe"));
+        assert!(reactor.is_destructive("unsafe"));
+        assert!(!reactor.is_destructive("unknown"));
+    }
+
+    #[test]
+    fn read_only() {
+        let mut x = 0;
+        let mut reactor = CmdReactor::<Context>::new();
+        reactor.add_commands([
+            (
+                "view",
+                (|ctx, _| {
+                    *ctx.number += 1;
+                    Ok(())
+                }) as super::FnCommand<Context>,
+            ),
+            ("amend", |ctx, _| {
+                *ctx.number += 10;
+                Ok(())
+            }),
+            ("exec_reload", |ctx, _| {
+                *ctx.number += 100;
+                Ok(())
+            }),
+        ]);
+        reactor.set_destructive("amend");
+        reactor.set_writes("exec_reload");
+        assert!(!reactor.is_destructive("exec_reload"));
+        reactor.set_read_only(true);
+
+        let mut d = Context { number: &mut x };
+        assert!(reactor.execute(&mut d, "view", vec![]).is_ok());
+        assert_eq!(
+            reactor.execute(&mut d, "amend", vec![]),
+            Err("amend: disabled in read-only mode".to_owned())
+        );
+        assert_eq!(
+            reactor.execute(&mut d, "exec_reload", vec!["git reset --hard".to_owned()]),
+            Err("exec_reload: disabled in read-only mode".to_owned())
+        );
+        reactor.set_read_only(false);
+        assert!(reactor.execute(&mut d, "amend", vec![]).is_ok());
+        assert!(reactor.execute(&mut d, "exec_reload", vec![]).is_ok());
+        assert_eq!(x, 111);
+    }
 }
@@ -0,0 +1,72 @@
+/// Holds an item waiting for user confirmation.
+pub struct Confirm<T> {
+    pending: Option<T>,
+}
+
+impl<T> Default for Confirm<T> {
+    fn default() -> Self {
+        Self { pending: None }
+    }
+}
+
+impl<T> Confirm<T> {
+    /// Starts waiting for confirmation, replaces previously pending item.
+    pub fn request(&mut self, item: T) {
+        self.pending = Some(item);
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Ends waiting, returns pending item only if confirmed.
+    pub fn answer(&mut self, confirmed: bool) -> Option<T> {
+        let item = self.pending.take();
+        if confirmed {
+            item
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Confirm;
+
+    #[test]
+    fn confirmed() {
+        let mut confirm = Confirm::default();
+        assert!(!confirm.is_pending());
+        confirm.request(1);
+        assert!(confirm.is_pending());
+        assert_eq!(confirm.answer(true), Some(1));
+        assert!(!confirm.is_pending());
+        // answer is consumed
+        assert_eq!(confirm.answer(true), None);
+    }
+
+    #[test]
+    fn rejected() {
+        let mut confirm = Confirm::default();
+        confirm.request(1);
+        assert_eq!(confirm.answer(false), None);
+        assert!(!confirm.is_pending());
+        assert_eq!(confirm.answer(true), None);
+    }
+
+    #[test]
+    fn request_replaces_pending() {
+        let mut confirm = Confirm::default();
+        confirm.request(1);
+        confirm.request(2);
+        assert_eq!(confirm.answer(true), Some(2));
+    }
+
+    #[test]
+    fn answer_without_request() {
+        let mut confirm = Confirm::<i32>::default();
+        assert_eq!(confirm.answer(true), None);
+        assert_eq!(confirm.answer(false), None);
+    }
+}
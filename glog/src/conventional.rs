@@ -0,0 +1,98 @@
+use ratatui::style::Color;
+
+/// Colors of Conventional Commits types used by `:set commit_types=on`
+pub const DEFAULT_TYPES: &str =
+    "feat=green,fix=red,perf=magenta,refactor=cyan,docs=blue,test=yellow";
+
+/// Splits Conventional Commits prefix (`type(scope)!: `) from subject, returns type, the whole
+/// prefix (without the space after colon) and the rest. None when subject has no such prefix.
+pub fn split_type(subject: &str) -> Option<(&str, &str, &str)> {
+    let type_end = subject
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .filter(|&end| end > 0)?;
+    let mut rest = &subject[type_end..];
+    if let Some(scope) = rest.strip_prefix('(') {
+        let scope_end = scope.find(')').filter(|&end| end > 0)?;
+        rest = &scope[scope_end + 1..];
+    }
+    rest = rest.strip_prefix('!').unwrap_or(rest);
+    rest = rest.strip_prefix(':')?;
+    let description = match rest.strip_prefix(' ') {
+        Some(description) => description,
+        None if rest.is_empty() => rest,
+        None => return None,
+    };
+    let prefix_end = subject.len() - rest.len();
+    Some((&subject[..type_end], &subject[..prefix_end], description))
+}
+
+/// Parses comma separated `type=color` pairs eg. "feat=green,fix=#ff0000", colors as in ratatui
+pub fn parse_types(value: &str) -> Result<Vec<(String, Color)>, String> {
+    value
+        .split(',')
+        .map(|pair| {
+            let (name, color) = pair
+                .trim()
+                .split_once('=')
+                .ok_or_else(|| format!("Expected type=color, got: {}", pair))?;
+            let color = color
+                .parse()
+                .map_err(|_| format!("Unknown color: {}", color))?;
+            Ok((name.to_owned(), color))
+        })
+        .collect()
+}
+
+/// Returns color of subject's type, None when it has no prefix or the type has no color
+pub fn type_color(types: &[(String, Color)], subject: &str) -> Option<Color> {
+    let (name, _, _) = split_type(subject)?;
+    types
+        .iter()
+        .find(|(t, _)| t.eq_ignore_ascii_case(name))
+        .map(|(_, color)| *color)
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::style::Color;
+
+    use super::{parse_types, split_type, type_color, DEFAULT_TYPES};
+
+    #[test]
+    fn prefix() {
+        assert_eq!(split_type("feat: x"), Some(("feat", "feat:", "x")));
+        assert_eq!(split_type("fix(core): y"), Some(("fix", "fix(core):", "y")));
+        assert_eq!(
+            split_type("refactor(ui)!: drop z"),
+            Some(("refactor", "refactor(ui)!:", "drop z"))
+        );
+        assert_eq!(split_type("chore:"), Some(("chore", "chore:", "")));
+
+        assert_eq!(split_type("Plain subject"), None);
+        assert_eq!(split_type("Merge branch 'x': y"), None);
+        assert_eq!(split_type("fix(): y"), None);
+        assert_eq!(split_type("fix(core y"), None);
+        assert_eq!(split_type(": y"), None);
+        assert_eq!(split_type("std::mem: y"), None);
+        assert_eq!(split_type(""), None);
+    }
+
+    #[test]
+    fn colors() {
+        let types = parse_types(DEFAULT_TYPES).unwrap();
+        assert_eq!(type_color(&types, "feat: x"), Some(Color::Green));
+        assert_eq!(type_color(&types, "Fix(core): y"), Some(Color::Red));
+        assert_eq!(type_color(&types, "chore: z"), None);
+        assert_eq!(type_color(&types, "plain subject"), None);
+
+        assert_eq!(
+            parse_types("ci=#ff0000, build=3"),
+            Ok(vec![
+                ("ci".to_owned(), Color::Rgb(255, 0, 0)),
+                ("build".to_owned(), Color::Indexed(3))
+            ])
+        );
+        assert!(parse_types("feat").is_err());
+        assert!(parse_types("feat=nocolor").is_err());
+    }
+}
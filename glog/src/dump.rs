@@ -0,0 +1,165 @@
+use std::{io::Write, path::Path};
+
+use tokio::pin;
+use tokio_stream::StreamExt;
+use unicode_width::UnicodeWidthChar;
+
+use crate::{
+    app::Entry,
+    graph,
+    oneline::{self, Field},
+};
+
+/// Date format used by tig layout, in the commit's own timezone so output is the same anywhere
+pub const TIG_DATE: &str = "format:%Y-%m-%d %H:%M";
+/// Author column of tig layout is padded or cut to this width
+const TIG_AUTHOR_WIDTH: usize = 20;
+const TIG_HASH_LEN: usize = 7;
+
+/// Layout of lines printed by --dump
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum DumpFormat {
+    /// list as shown by glog without colors: graph, hash, refs, subject, (author, date)
+    #[default]
+    Glog,
+    /// columns as in tig main view separated by single space: hash (7 characters), date
+    /// (YYYY-MM-DD HH:MM), author (padded or cut to 20 columns) and subject; there's no graph
+    /// so rows made only of graph are skipped
+    Tig,
+}
+
+/// Returns dumped line of entry, None when the layout skips it
+pub fn line(entry: &Entry, format: DumpFormat) -> Option<String> {
+    match format {
+        DumpFormat::Glog => Some(glog_line(entry)),
+        DumpFormat::Tig => tig_line(entry),
+    }
+}
+
+fn glog_line(entry: &Entry) -> String {
+    let mut line = String::new();
+    for field in oneline::fields(entry, Default::default()) {
+        match field {
+            Field::Graph(graph) => line.push_str(&graph::strip_sgr(graph)),
+            Field::Hash(hash) => {
+                line.push_str(hash);
+                line.push(' ');
+            }
+            Field::Refs(refs) => {
+                let names: Vec<_> = refs.into_iter().map(|(_, name)| name).collect();
+                line.push_str(&format!("({}) ", names.join(", ")));
+            }
+            Field::Subject(subject) => {
+                line.push_str(subject);
+                line.push(' ');
+            }
+            Field::AuthorDate(author_date) => line.push_str(&author_date),
+            // not enabled by default options
+            Field::Selection(_) | Field::Age(_) | Field::Initials(..) | Field::ReachedBy(_) => {}
+        }
+    }
+    line.trim_end().to_owned()
+}
+
+fn tig_line(entry: &Entry) -> Option<String> {
+    let git = &entry.git;
+    if git.hash.is_empty() {
+        return None;
+    }
+    let hash = &git.hash[..std::cmp::min(TIG_HASH_LEN, git.hash.len())];
+    let author = pad_columns(&git.author, TIG_AUTHOR_WIDTH);
+    Some(format!("{} {} {} {}", hash, git.date, author, git.subject))
+}
+
+/// Cuts text to width columns, shorter text is padded with spaces
+fn pad_columns(text: &str, width: usize) -> String {
+    let mut padded = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        used += w;
+        padded.push(c);
+    }
+    padded.push_str(&" ".repeat(width - used));
+    padded
+}
+
+/// Writes log as given layout, tig layout uses its own date format whatever --date says
+pub async fn dump(
+    repository: &Path,
+    revision_range: &[String],
+    mut options: git::LogOptions,
+    format: DumpFormat,
+    out: &mut impl Write,
+) -> Result<(), std::io::Error> {
+    if format == DumpFormat::Tig {
+        options.date = Some(TIG_DATE.to_owned());
+    }
+    let stream = git::get_log_data(repository, revision_range, &options).await?;
+    pin!(stream);
+    while let Some(entry) = stream.next().await {
+        let entry = Entry::new(entry?);
+        if let Some(line) = line(&entry, format) {
+            writeln!(out, "{}", line)?;
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{line, pad_columns, DumpFormat};
+    use crate::app::Entry;
+
+    fn entry(hash: &str, author: &str, subject: &str) -> Entry {
+        Entry::new(git::LogEntry {
+            graph: "\x1b[31m*\x1b[0m ".to_owned(),
+            hash: hash.to_owned(),
+            author: author.to_owned(),
+            date: "2024-02-29 13:05".to_owned(),
+            subject: subject.to_owned(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn tig_layout() {
+        let hash = "d401a57c0ffee0000000000000000000000000000";
+        assert_eq!(
+            line(&entry(hash, "Jane Doe", "Fix parser"), DumpFormat::Tig).as_deref(),
+            Some("d401a57 2024-02-29 13:05 Jane Doe             Fix parser")
+        );
+        let long = "Bartholomew Longname-Smith";
+        assert_eq!(
+            line(&entry(hash, long, "x"), DumpFormat::Tig).as_deref(),
+            Some("d401a57 2024-02-29 13:05 Bartholomew Longname x")
+        );
+        // graph only row
+        assert_eq!(line(&entry("", "", ""), DumpFormat::Tig), None);
+    }
+
+    #[test]
+    fn glog_layout() {
+        let hash = "d401a57c0ffee0000000000000000000000000000";
+        assert_eq!(
+            line(&entry(hash, "Jane Doe", "Fix parser"), DumpFormat::Glog).as_deref(),
+            Some("* d401a57c Fix parser (Jane Doe, 2024-02-29 13:05)")
+        );
+        assert_eq!(
+            line(&entry("", "", ""), DumpFormat::Glog).as_deref(),
+            Some("*")
+        );
+    }
+
+    #[test]
+    fn padding() {
+        assert_eq!(pad_columns("ab", 4), "ab  ");
+        assert_eq!(pad_columns("abcdef", 4), "abcd");
+        // wide character which does not fit is replaced by padding
+        assert_eq!(pad_columns("abc日", 4), "abc ");
+        assert_eq!(pad_columns("日本", 4), "日本");
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+
+/// Folds merged side branches so only first-parent history of every tip stays visible.
+/// Entries have to be fed in log order where children come before parents, which is guaranteed
+/// by `--graph` (it implies `--topo-order`).
+#[derive(Debug, Default)]
+pub struct FirstParentFold {
+    /// first parents of visible commits, these are never folded
+    first_parents: HashSet<String>,
+    /// commits reached through second and further parents of merges (or of folded commits)
+    side: HashSet<String>,
+}
+
+impl FirstParentFold {
+    /// Returns whether entry stays visible, graph only rows are always folded
+    pub fn visible(&mut self, entry: &git::LogEntry) -> bool {
+        if entry.hash.is_empty() {
+            return false;
+        }
+        let visible = !self.side.contains(&entry.hash) || self.first_parents.contains(&entry.hash);
+        let mut parents = entry.parents.iter();
+        if visible {
+            if let Some(first) = parents.next() {
+                self.first_parents.insert(first.clone());
+            }
+        }
+        self.side.extend(parents.cloned());
+        visible
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FirstParentFold;
+
+    fn commit(hash: &str, parents: &[&str]) -> git::LogEntry {
+        git::LogEntry {
+            hash: hash.to_owned(),
+            parents: parents.iter().map(|p| (*p).to_owned()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn visible(entries: &[git::LogEntry]) -> Vec<&str> {
+        let mut fold = FirstParentFold::default();
+        entries
+            .iter()
+            .filter(|e| fold.visible(e))
+            .map(|e| e.hash.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn side_branches_are_folded() {
+        // m2 merges f2 (f2 -> f1 -> b), m1 merges g1 which branched from a
+        let entries = [
+            commit("m2", &["c", "f2"]),
+            commit("f2", &["f1"]),
+            git::LogEntry::default(), // graph only row
+            commit("c", &["m1"]),
+            commit("f1", &["b"]),
+            commit("m1", &["b", "g1"]),
+            commit("g1", &["a"]),
+            commit("b", &["a"]),
+            commit("a", &[]),
+        ];
+        assert_eq!(visible(&entries), ["m2", "c", "m1", "b", "a"]);
+    }
+
+    #[test]
+    fn every_tip_keeps_its_first_parent_history() {
+        let entries = [
+            commit("x2", &["x1"]),
+            commit("y1", &["b"]),
+            commit("x1", &["b"]),
+            commit("b", &[]),
+        ];
+        assert_eq!(visible(&entries), ["x2", "y1", "x1", "b"]);
+    }
+}
@@ -0,0 +1,123 @@
+use ratatui::{
+    style::{Color, Style},
+    text::Span,
+};
+
+/// How colors of graph are rendered
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum GraphColors {
+    /// colors produced by git, converted from ANSI
+    #[default]
+    Ansi,
+    /// git colors are dropped and glyphs are colored by their kind
+    Glyphs,
+}
+
+pub fn parse_graph_colors(value: &str) -> Result<GraphColors, String> {
+    match value {
+        "ansi" => Ok(GraphColors::Ansi),
+        "glyphs" => Ok(GraphColors::Glyphs),
+        _ => Err(format!("Expected ansi or glyphs, got: {}", value)),
+    }
+}
+
+/// Removes SGR escape sequences (`ESC [ ... m`), other text is kept as is
+pub fn strip_sgr(graph: &str) -> String {
+    let mut plain = String::with_capacity(graph.len());
+    let mut chars = graph.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.peek() == Some(&'[') {
+            chars.next();
+            // parameters are digits and ';', anything else ends the sequence
+            for c in chars.by_ref() {
+                if !(c.is_ascii_digit() || c == ';') {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+pub fn glyph_color(glyph: char) -> Option<Color> {
+    match glyph {
+        '*' => Some(Color::Yellow),
+        '|' => Some(Color::Blue),
+        '/' | '\\' => Some(Color::Cyan),
+        '_' => Some(Color::Magenta),
+        _ => None,
+    }
+}
+
+/// Returns graph without git colors, runs of glyphs of the same kind share a span
+pub fn glyph_spans(graph: &str) -> Vec<Span<'static>> {
+    let mut spans: Vec<(Option<Color>, String)> = Vec::new();
+    for c in strip_sgr(graph).chars() {
+        let color = glyph_color(c);
+        match spans.last_mut() {
+            Some((last, text)) if *last == color => text.push(c),
+            _ => spans.push((color, c.to_string())),
+        }
+    }
+    spans
+        .into_iter()
+        .map(|(color, text)| match color {
+            Some(color) => Span::styled(text, Style::default().fg(color)),
+            None => Span::raw(text),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::{
+        style::{Color, Style},
+        text::Span,
+    };
+
+    use super::{glyph_color, glyph_spans, parse_graph_colors, strip_sgr, GraphColors};
+
+    #[test]
+    fn sgr_is_stripped() {
+        assert_eq!(strip_sgr("\x1b[31m|\x1b[m \x1b[1;32m*\x1b[0m"), "| *");
+        assert_eq!(strip_sgr("| * "), "| * ");
+        // lone escape is not a sequence
+        assert_eq!(strip_sgr("\x1b|"), "\x1b|");
+    }
+
+    #[test]
+    fn glyph_colors() {
+        assert_eq!(glyph_color('*'), Some(Color::Yellow));
+        assert_eq!(glyph_color('|'), Some(Color::Blue));
+        assert_eq!(glyph_color('/'), Some(Color::Cyan));
+        assert_eq!(glyph_color('\\'), Some(Color::Cyan));
+        assert_eq!(glyph_color('_'), Some(Color::Magenta));
+        assert_eq!(glyph_color(' '), None);
+        assert_eq!(glyph_color('-'), None);
+    }
+
+    #[test]
+    fn spans_of_graph() {
+        let style = |color| Style::default().fg(color);
+        assert_eq!(
+            glyph_spans("\x1b[31m|\x1b[m\x1b[32m|\x1b[m \x1b[33m*\x1b[m\\ "),
+            [
+                Span::styled("||", style(Color::Blue)),
+                Span::raw(" "),
+                Span::styled("*", style(Color::Yellow)),
+                Span::styled("\\", style(Color::Cyan)),
+                Span::raw(" "),
+            ]
+        );
+        assert!(glyph_spans("").is_empty());
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_graph_colors("ansi"), Ok(GraphColors::Ansi));
+        assert_eq!(parse_graph_colors("glyphs"), Ok(GraphColors::Glyphs));
+        assert!(parse_graph_colors("none").is_err());
+    }
+}
@@ -0,0 +1,127 @@
+/// Number of commands kept (and persisted), oldest are dropped first
+pub const MAX_HISTORY: usize = 200;
+
+/// History of command mode lines, browsed from the newest one.
+#[derive(Debug, Default)]
+pub struct History {
+    entries: Vec<String>,
+    /// entry currently shown in command line, None when not browsing
+    cursor: Option<usize>,
+    /// line typed before browsing started, shown again once browsing gets past the newest
+    draft: String,
+}
+
+impl History {
+    pub fn from_entries(mut entries: Vec<String>) -> Self {
+        entries.drain(..entries.len().saturating_sub(MAX_HISTORY));
+        Self {
+            entries,
+            cursor: None,
+            draft: String::new(),
+        }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Adds executed line, empty lines and repetitions of the last one are skipped
+    pub fn push(&mut self, line: &str) {
+        self.cursor = None;
+        if line.trim().is_empty() || self.entries.last().is_some_and(|l| l == line) {
+            return;
+        }
+        if self.entries.len() >= MAX_HISTORY {
+            self.entries.remove(0);
+        }
+        self.entries.push(line.to_owned());
+    }
+
+    /// Returns older entry, stays at the oldest one. Line is kept as draft when browsing starts.
+    pub fn prev(&mut self, line: &str) -> Option<&str> {
+        let cursor = match self.cursor {
+            Some(cursor) => cursor.saturating_sub(1),
+            None => {
+                let cursor = self.entries.len().checked_sub(1)?;
+                self.draft = line.to_owned();
+                cursor
+            }
+        };
+        self.cursor = Some(cursor);
+        self.entries.get(cursor).map(String::as_str)
+    }
+
+    /// Returns newer entry, draft once browsing gets past the newest and None when not browsing
+    /// so the line stays as it is
+    pub fn next(&mut self) -> Option<&str> {
+        let cursor = self.cursor? + 1;
+        if cursor >= self.entries.len() {
+            self.cursor = None;
+            return Some(&self.draft);
+        }
+        self.cursor = Some(cursor);
+        self.entries.get(cursor).map(String::as_str)
+    }
+
+    pub fn reset_cursor(&mut self) {
+        self.cursor = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{History, MAX_HISTORY};
+
+    #[test]
+    fn browse() {
+        let mut history = History::default();
+        assert_eq!(history.prev(""), None);
+        assert_eq!(history.next(), None);
+
+        history.push("reload");
+        history.push("reload");
+        history.push(" ");
+        history.push("echo x");
+        assert_eq!(history.entries(), ["reload", "echo x"]);
+
+        assert_eq!(history.prev(""), Some("echo x"));
+        assert_eq!(history.prev(""), Some("reload"));
+        assert_eq!(history.prev(""), Some("reload"));
+        assert_eq!(history.next(), Some("echo x"));
+        assert_eq!(history.next(), Some(""));
+        assert_eq!(history.next(), None);
+        assert_eq!(history.prev(""), Some("echo x"));
+
+        // pushing ends browsing
+        history.push("top");
+        assert_eq!(history.prev(""), Some("top"));
+        history.reset_cursor();
+        assert_eq!(history.next(), None);
+    }
+
+    #[test]
+    fn draft() {
+        let mut history = History::from_entries(vec!["reload".to_owned(), "top".to_owned()]);
+        // typed line is left alone when not browsing
+        assert_eq!(history.next(), None);
+
+        assert_eq!(history.prev("goto_li"), Some("top"));
+        // draft is kept from the start of browsing
+        assert_eq!(history.prev("top"), Some("reload"));
+        assert_eq!(history.next(), Some("top"));
+        assert_eq!(history.next(), Some("goto_li"));
+        assert_eq!(history.next(), None);
+    }
+
+    #[test]
+    fn bounded() {
+        let mut history =
+            History::from_entries((0..MAX_HISTORY + 5).map(|i| i.to_string()).collect());
+        assert_eq!(history.entries().len(), MAX_HISTORY);
+        assert_eq!(history.entries()[0], "5");
+        history.push("new");
+        assert_eq!(history.entries().len(), MAX_HISTORY);
+        assert_eq!(history.entries()[0], "6");
+        assert_eq!(history.prev(""), Some("new"));
+    }
+}
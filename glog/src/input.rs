@@ -1,31 +1,48 @@
+use std::time::Duration;
+
 use crossterm::event::EventStream;
-use futures::{select, FutureExt, StreamExt};
+use futures::StreamExt;
 use log::debug;
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+/// How often Tick is emitted when there's no input
+const TICK_RATE: Duration = Duration::from_millis(250);
 
+#[derive(Debug)]
 pub enum InputEvent {
     Event(crossterm::event::Event),
+    /// periodic tick from timer, independent of input
     Tick,
+    /// terminal event stream ended so no more input will come
+    Closed,
 }
 pub struct Input {
     event_stream: EventStream,
+    tick: Interval,
 }
 
 impl Input {
     pub fn new() -> Input {
+        let mut tick = interval(TICK_RATE);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
         Input {
             event_stream: EventStream::new(),
+            tick,
         }
     }
 
+    /// Returns next event, it's cancellation safe so it can be used in select! loop
     pub async fn next(&mut self) -> InputEvent {
-        let mut event = self.event_stream.next().fuse();
-        select! {
-            maybe_event = event => {
-                match maybe_event {
-                    Some(Ok(x)) => InputEvent::Event(x),
-                    Some(Err(e)) => { debug!("Error: {:?}\r", e); InputEvent::Tick }
-                    None => InputEvent::Tick,
+        loop {
+            tokio::select! {
+                maybe_event = self.event_stream.next() => {
+                    match maybe_event {
+                        Some(Ok(x)) => return InputEvent::Event(x),
+                        Some(Err(e)) => debug!("Error: {:?}\r", e),
+                        None => return InputEvent::Closed,
+                    }
                 }
+                _ = self.tick.tick() => return InputEvent::Tick,
             }
         }
     }
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use crossterm::event::KeyEvent;
+
+/// Registers which can hold a macro
+pub const REGISTERS: std::ops::RangeInclusive<char> = 'a'..='z';
+/// Limit of nested replays so macro calling itself doesn't loop forever
+pub const MAX_DEPTH: usize = 10;
+
+/// Vim-like macros: normal mode keys recorded into registers and replayed on request.
+#[derive(Debug, Default)]
+pub struct Macros {
+    registers: HashMap<char, Vec<KeyEvent>>,
+    /// register and keys being recorded
+    recording: Option<(char, Vec<KeyEvent>)>,
+    /// register requested to be replayed, keys are dispatched by main loop
+    replay: Option<char>,
+    depth: usize,
+}
+
+impl Macros {
+    pub fn start_recording(&mut self, register: char) {
+        self.recording = Some((register, Vec::new()));
+    }
+
+    /// Stores recorded keys into register and returns it
+    pub fn stop_recording(&mut self) -> Option<char> {
+        let (register, keys) = self.recording.take()?;
+        self.registers.insert(register, keys);
+        Some(register)
+    }
+
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    /// Records key unless it comes from a replay
+    pub fn record(&mut self, key: KeyEvent) {
+        if self.depth == 0 {
+            if let Some((_, keys)) = &mut self.recording {
+                keys.push(key);
+            }
+        }
+    }
+
+    pub fn request_replay(&mut self, register: char) -> Result<(), String> {
+        if !self.registers.contains_key(&register) {
+            return Err(format!("Register @{} is empty", register));
+        }
+        self.replay = Some(register);
+        Ok(())
+    }
+
+    pub fn take_replay(&mut self) -> Option<Vec<KeyEvent>> {
+        let register = self.replay.take()?;
+        self.registers.get(&register).cloned()
+    }
+
+    /// Marks start of a replay, returns false when nested too deep
+    pub fn enter(&mut self) -> bool {
+        if self.depth >= MAX_DEPTH {
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    pub fn leave(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::{Macros, MAX_DEPTH};
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let mut macros = Macros::default();
+        assert!(macros.request_replay('a').is_err());
+
+        // keys outside of recording are ignored
+        macros.record(key('x'));
+        macros.start_recording('a');
+        assert_eq!(macros.recording(), Some('a'));
+        macros.record(key('j'));
+        macros.record(key('j'));
+        assert_eq!(macros.stop_recording(), Some('a'));
+        assert_eq!(macros.recording(), None);
+        assert_eq!(macros.stop_recording(), None);
+
+        assert_eq!(macros.take_replay(), None);
+        assert!(macros.request_replay('a').is_ok());
+        assert_eq!(macros.take_replay(), Some(vec![key('j'), key('j')]));
+        assert_eq!(macros.take_replay(), None);
+    }
+
+    #[test]
+    fn replayed_keys_are_not_recorded() {
+        let mut macros = Macros::default();
+        macros.start_recording('b');
+        macros.record(key('@'));
+        assert!(macros.enter());
+        macros.record(key('j'));
+        macros.leave();
+        macros.stop_recording();
+        macros.request_replay('b').unwrap();
+        assert_eq!(macros.take_replay(), Some(vec![key('@')]));
+    }
+
+    #[test]
+    fn depth_limit() {
+        let mut macros = Macros::default();
+        for _ in 0..MAX_DEPTH {
+            assert!(macros.enter());
+        }
+        assert!(!macros.enter());
+        macros.leave();
+        assert!(macros.enter());
+    }
+}
@@ -1,18 +1,38 @@
+mod accel;
 mod actions;
+mod age;
 mod app;
+mod autoplay;
+mod clock;
 mod cmdreactor;
+mod confirm;
+mod conventional;
+mod dump;
+mod fold;
+mod graph;
+mod history;
 mod input;
+mod macros;
+mod oneline;
+mod osc52;
+mod osc8;
+mod select_hook;
+mod state;
 mod stateful_list;
 mod term;
+mod trace;
 mod ui;
 mod utils;
 
-use app::App;
+use app::{App, StartAt};
 use cmdreactor::CommandResult;
 use input::InputEvent;
 use log::trace;
 use std::error::Error;
-use tokio::select;
+use tokio::{
+    select,
+    signal::unix::{signal, SignalKind},
+};
 use tui_textarea::{Input, Key};
 
 use clap::Parser;
@@ -32,8 +52,98 @@ struct Cli {
     /// git repository path
     #[clap(short, long)]
     repository: Option<String>,
-    /// as specified in git-log command eg. HEAD "^HEAD~5"
+    /// date format as in git-log --date eg. short, iso, relative or "format:%Y-%m-%d"
+    #[clap(long, value_parser = parse_date_format)]
+    date: Option<String>,
+    /// where cursor lands after loading
+    #[clap(long, value_enum, default_value_t = StartAt::Top)]
+    start_at: StartAt,
+    /// hide merge commits as git-log --no-merges
+    #[clap(long)]
+    no_merges: bool,
+    /// follow only the first parent of merges as git-log --first-parent
+    #[clap(long)]
+    first_parent: bool,
+    /// keep only fields shown in the list (hash, subject, author, date, graph and refs) to save
+    /// memory on huge histories, parents and reached_by are dropped so merge and node navigation,
+    /// goto_child and folding are not available; details are fetched on demand
+    #[clap(long)]
+    compact: bool,
+    /// define command running shell template eg. --command 'fixup=git commit --fixup %0',
+    /// may be repeated
+    #[clap(long = "command", value_name = "NAME=TEMPLATE", value_parser = parse_command)]
+    commands: Vec<(String, String)>,
+    /// do not read nor write command history and marks kept per repository between sessions
+    #[clap(long)]
+    no_state: bool,
+    /// demo mode: when no key is pressed for given milliseconds cursor moves one line down,
+    /// any key pauses it until idle again, off by default
+    #[clap(long, value_name = "MS")]
+    autoplay: Option<u64>,
+    /// autoplay starts again from the top instead of stopping at the end of the list
+    #[clap(long, requires = "autoplay")]
+    autoplay_wrap: bool,
+    /// append JSON lines with input events, resolved key bindings, executed commands and loader
+    /// events to given file, meant for debugging
+    #[clap(long, value_name = "PATH")]
+    trace_events: Option<std::path::PathBuf>,
+    /// capture mouse: click on author of a row lists only commits of that author (see
+    /// author_filter), terminal's own text selection usually needs Shift then
+    #[clap(long)]
+    mouse: bool,
+    /// print the log to stdout and exit instead of starting the UI
+    #[clap(long)]
+    dump: bool,
+    /// layout of --dump output
+    #[clap(long, value_enum, requires = "dump")]
+    format: Option<dump::DumpFormat>,
+    /// do not ask for confirmation of destructive commands
+    #[clap(long)]
+    yes: bool,
+    /// refuse commands changing repository state (reword, reword_head, restore_file and
+    /// exec_reload run by `!!`), exec and commands given by --command are intentionally still
+    /// allowed as they're mostly used for viewing
+    #[clap(long, conflicts_with = "yes")]
+    read_only: bool,
+    /// as specified in git-log command eg. HEAD "^HEAD~5", a single sha of existing commit loads
+    /// all refs and starts on that commit
     revision_range: Vec<String>,
+    /// limit log to commits touching given paths, eg. glog HEAD -- src
+    #[clap(last = true)]
+    paths: Vec<String>,
+}
+
+fn parse_date_format(format: &str) -> Result<String, String> {
+    if git::is_valid_date_format(format) {
+        Ok(format.to_owned())
+    } else {
+        Err(format!("Unknown date format: {format}"))
+    }
+}
+
+fn parse_command(definition: &str) -> Result<(String, String), String> {
+    let (name, template) = definition
+        .split_once('=')
+        .ok_or_else(|| "Expected NAME=TEMPLATE".to_owned())?;
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!("Invalid command name: {name:?}"));
+    }
+    app::parse_template(actions::exec_template(template))?;
+    Ok((name.to_owned(), template.to_owned()))
+}
+
+/// Returns top-level of repository enclosing current directory, git resolves it so GIT_DIR and
+/// GIT_WORK_TREE are honored. Outside of a repository it's the current directory and git
+/// reports the error once log is loaded.
+fn default_repository() -> std::io::Result<std::path::PathBuf> {
+    let cwd = std::env::current_dir()?;
+    match git::get_toplevel(&cwd) {
+        Ok(toplevel) => Ok(toplevel.into()),
+        Err(e) => {
+            log::warn!("No repository found from {}: {}", cwd.display(), e);
+            Ok(cwd)
+        }
+    }
 }
 
 #[allow(clippy::single_match)]
@@ -46,17 +156,43 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
 
     let cli = Cli::parse();
-    let repository = std::fs::canonicalize(cli.repository.unwrap_or_else(|| "./".to_string()))?;
+    let repository = match cli.repository {
+        Some(path) => std::fs::canonicalize(path)?,
+        None => default_repository()?,
+    };
+    let log_options = git::LogOptions {
+        date: cli.date,
+        no_merges: cli.no_merges,
+        first_parent: cli.first_parent,
+        paths: cli.paths,
+        ..Default::default()
+    };
+
+    if cli.dump {
+        let format = cli.format.unwrap_or_default();
+        let mut out = std::io::stdout().lock();
+        dump::dump(
+            &repository,
+            &cli.revision_range,
+            log_options,
+            format,
+            &mut out,
+        )
+        .await?;
+        return Ok(());
+    }
 
     // TODO: bind via config file
     // TODO: <cr> executes commands, othewise enter pre-filled command mode
     // TODO: allow shorter commands when not conflicting
-    // TODO: allow way to bind new commands MAP COMMAND!
     // TODO: help command!
     let mut parser = VimKeyParser::default();
     parser
+        .enable_count(true)
         .add_action("q", "quit".to_owned())
         .add_action("<c-c>", "quit".to_owned())
+        .add_action("<f1>", "explain_key".to_owned())
+        .add_action("<esc>", "dismiss".to_owned())
         .add_action("k", "up".to_owned())
         .add_action("j", "down".to_owned())
         .add_action("<c-u>", "pageup".to_owned())
@@ -65,36 +201,149 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .add_action("G", "bottom".to_owned())
         .add_action("K", "nodeup".to_owned())
         .add_action("J", "nodedown".to_owned())
-        .add_action("L", "exec git show --stat --patch %0".to_owned())
+        .add_action("[m", "mergeup".to_owned())
+        .add_action("]m", "mergedown".to_owned())
+        .add_action("[t", "tagup".to_owned())
+        .add_action("]t", "tagdown".to_owned())
+        .add_action("L", "exec_show".to_owned())
+        .add_action("gh", "goto_head".to_owned())
+        .add_action("gc", "goto_child".to_owned())
+        .add_action("gs", "show %0".to_owned())
+        // g rather than d prefix (dr, dw): d alone diffs selection so it would wait for timeout
+        .add_action("gr", "refresh_diff".to_owned())
+        .add_action("gw", "diff_worktree %0".to_owned())
+        .add_action("gp", "diff_prev_visited".to_owned())
         .add_action("yy", "yank %0".to_owned())
+        .add_action("yr", "yank_ref_line".to_owned())
+        .add_action("ym", "yank_message".to_owned())
+        .add_action("yd", "yank_diff %0".to_owned())
+        .add_action("yY", "yank_selected".to_owned())
         // TODO: something like %0:branch[@] which would return branch name
         .add_action("zz", "center".to_owned())
+        .add_action("zh", "toggle_full_hash".to_owned())
+        .add_action("zf", "toggle_fold".to_owned())
+        .add_action("zg", "toggle_group".to_owned())
+        .add_action("zw", "toggle_wrap".to_owned())
+        .add_action("zr", "reset_view".to_owned())
+        .add_action("zl", "scroll_right".to_owned())
+        .add_action("z0", "scroll_home".to_owned())
+        .add_action("<right>", "scroll_right".to_owned())
+        .add_action("<left>", "scroll_left".to_owned())
+        .add_action("<home>", "scroll_home".to_owned())
         .add_action("<space>", "select".to_owned())
         .add_action("d", "exec git diff %_1 %0 ".to_owned())
         .add_action("D", "exec git difftool --dir-diff %_1 %0".to_owned())
+        .add_action("cw", "reword".to_owned())
         // .add_action("@", "exec %@") // TODO: this should enter command without triggering it
         .add_action("/", "search".to_owned())
         .add_action(":", "mode command".to_owned())
+        .add_action("s", "mode jump".to_owned())
         .add_action("r", "enter_reload".to_owned());
+    for register in macros::REGISTERS {
+        parser
+            .add_action(&format!("q{register}"), format!("macro_record {register}"))
+            .add_action(&format!("@{register}"), format!("macro_play {register}"))
+            .add_action(&format!("m{register}"), format!("mark {register}"))
+            .add_action(&format!("'{register}"), format!("goto_mark {register}"));
+    }
 
     let mut cmd_reactor = CmdReactor::new();
     cmd_reactor.add_commands(actions::actions());
+    for name in actions::destructive_actions() {
+        cmd_reactor.set_destructive(name);
+    }
+    for name in actions::writing_actions() {
+        cmd_reactor.set_writes(name);
+    }
+    cmd_reactor.set_read_only(cli.read_only);
+    for (name, template) in cli.commands {
+        cmd_reactor
+            .add_data_command(&name, actions::templated_exec, template)
+            .map_err(|e| format!("--command {name}: {e}"))?;
+    }
+
+    // `glog <sha>` shows the commit among all refs, so its children are visible too
+    let mut revision_range = cli.revision_range;
+    let mut start_sha = None;
+    if let Some(arg) = app::sha_argument(&revision_range) {
+        match git::rev_parse(&repository, &format!("{}^{{commit}}", arg)) {
+            // name of a ref made only of hex digits resolves to another sha
+            Ok(sha) if sha.starts_with(&arg.to_ascii_lowercase()) => {
+                start_sha = Some(sha);
+                revision_range = vec!["--all".to_owned()];
+            }
+            _ => {}
+        }
+    }
+
+    let mut app = App::new(repository, revision_range, log_options);
+    app.start_at = cli.start_at;
+    app.start_sha = start_sha;
+    app.confirm_destructive = !cli.yes;
+    app.compact = cli.compact;
+    if let Some(path) = &cli.trace_events {
+        app.trace = trace::EventTrace::open(path)
+            .map_err(|e| format!("--trace-events {}: {e}", path.display()))?;
+    }
+    app.autoplay = cli.autoplay.map(|ms| {
+        autoplay::Autoplay::new(
+            std::time::Duration::from_millis(ms),
+            cli.autoplay_wrap,
+            std::time::Instant::now(),
+        )
+    });
+
+    let state_path = if cli.no_state {
+        None
+    } else {
+        app.resolve_repository_path()
+            .ok()
+            .and_then(|toplevel| state::state_path(std::path::Path::new(&toplevel)))
+    };
+    if let Some(path) = &state_path {
+        let state = state::State::load(path);
+        app.history = history::History::from_entries(state.history);
+        app.marks = state.marks;
+    }
+
+    app.default_maps = parser
+        .get_actions()
+        .into_iter()
+        .map(|(binding, action)| (binding, action.to_owned()))
+        .collect();
 
     let context = actions::Context {
-        app: App::new(repository, cli.revision_range),
+        app,
         clipboard: copypasta::ClipboardContext::new().ok(),
         input: input::Input::new(),
-        term: Term::new()?,
+        term: Term::new(cli.mouse)?,
         parser,
     };
 
-    mainloop(context, cmd_reactor).await
+    mainloop(context, cmd_reactor, state_path).await
+}
+
+/// Saves state on quit, failure is only logged as state is not essential
+fn save_state(app: &App, path: &std::path::Path) {
+    let state = state::State {
+        history: app.history.entries().to_vec(),
+        marks: app.marks.clone(),
+    };
+    if let Err(e) = state.save(path) {
+        log::warn!("Saving state to {:?} failed: {}", path, e);
+    }
 }
 
 async fn mainloop<'a>(
     mut context: actions::Context<'a>,
     mut cmd_reactor: CmdReactor<actions::Context<'a>>,
+    state_path: Option<std::path::PathBuf>,
 ) -> Result<(), Box<dyn Error>> {
+    // signals sent by OS (eg. from parent process) bypass key bindings so treat them as quit;
+    // leaving the loop drops Term which restores the terminal
+    let mut sigint = signal(SignalKind::interrupt())?;
+    let mut sigterm = signal(SignalKind::terminate())?;
+
     context.app.reload(None);
 
     while !context.app.should_quit {
@@ -103,13 +352,29 @@ async fn mainloop<'a>(
         // TODO: rendering should not happen each frame, more like with delay of 30ms so more frames are grouped together
         context.render()?;
 
+        let key_deadline = context.app.key_deadline;
         select! {
             _ = context.app.process() => {},
             event = context.input.next() => {
                 handle_input_event(event, &mut context, &mut cmd_reactor);
             },
+            _ = tokio::time::sleep_until(
+                key_deadline.map_or_else(tokio::time::Instant::now, tokio::time::Instant::from_std)
+            ), if key_deadline.is_some() => {
+                if context.app.take_key_timeout() {
+                    let parsed = context.parser.resolve_timeout();
+                    handle_parsed_action(parsed, &mut context, &mut cmd_reactor);
+                }
+            },
+            _ = sigint.recv() => context.app.should_quit = true,
+            _ = sigterm.recv() => context.app.should_quit = true,
         }
+        context.app.note_visited();
+    }
+    if let Some(path) = &state_path {
+        save_state(&context.app, path);
     }
+    context.app.shutdown().await;
     Ok(())
 }
 
@@ -120,10 +385,10 @@ fn execute<'a>(
 ) {
     let mut inner_fn = || -> CommandResult {
         // pre-process
-        let line = if let Some(stripped) = line.strip_prefix('!') {
-            format!("exec {}", stripped)
+        let line = if is_line_target(line) {
+            format!("goto_line {}", line)
         } else {
-            line.to_owned()
+            app::expand_shell_prefix(line, &ctx.app.shell_prefix, &ctx.app.shell_reload_prefix)
         };
 
         let words = shlex::split(&line).ok_or_else(|| "Failed to parse command line".to_owned())?;
@@ -131,42 +396,61 @@ fn execute<'a>(
             .first()
             .ok_or_else(|| "There's no name in command line".to_owned())?;
 
+        // feedkeys gets text verbatim so placeholders are resolved once command line is executed
+        let raw_args = name == "feedkeys";
         let args: Result<Vec<_>, _> = words
             .iter()
             .skip(1)
             .filter_map(|a| -> Option<Result<String, String>> {
-                match a.as_str() {
-                    "%0" => {
-                        let v = ctx
-                            .app
-                            .current_sha() //asdf
-                            .ok_or_else(|| "No sha".to_owned());
-                        Some(v)
-                    }
-                    "%_1" => {
-                        // if there's no selected0 this will be None and will be filtered
-                        ctx.app.log.selected0().map(|e| Ok(e.git.hash.clone()))
-                    }
-                    "%1" => {
-                        let v = ctx
-                            .app
-                            .log
-                            .selected0()
-                            .map(|e| e.git.hash.clone())
-                            .ok_or_else(|| "No selection".to_owned());
-                        Some(v)
-                    }
-                    "%%" => Some(Ok("%".to_owned())),
-                    _ => Some(Ok(a.to_owned())),
+                if raw_args {
+                    return Some(Ok(a.to_owned()));
                 }
+                ctx.app.resolve_placeholder(a)
             })
             .collect();
         let args = args?;
 
-        cmd_reactor.execute(ctx, name, args)
+        let confirm = ctx.app.confirm_destructive && !cmd_reactor.is_read_only();
+        if confirm && cmd_reactor.is_destructive(name) {
+            ctx.app.status = format!("{}: confirm (y/n)", line);
+            ctx.app.confirm.request((name.to_owned(), args));
+            return Ok(());
+        }
+        run_command(ctx, cmd_reactor, name, args)
     };
 
-    match inner_fn() {
+    let result = inner_fn();
+    ctx.app
+        .trace
+        .record("command", || format!("{} -> {:?}", line, result));
+    report(ctx, result);
+}
+
+/// Command line like :12, :+3 or :-3
+fn is_line_target(line: &str) -> bool {
+    let digits = line.strip_prefix(['+', '-']).unwrap_or(line);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Executes command, failure of destructive one is pinned until dismissed (e.g. a conflict
+/// that has to be resolved) so following actions don't overwrite it
+fn run_command<'a>(
+    ctx: &mut actions::Context<'a>,
+    cmd_reactor: &mut CmdReactor<actions::Context<'a>>,
+    name: &str,
+    args: Vec<String>,
+) -> CommandResult {
+    let result = cmd_reactor.execute(ctx, name, args);
+    if let Err(e) = &result {
+        if cmd_reactor.is_destructive(name) && !cmd_reactor.is_read_only() {
+            ctx.app.sticky = Some(e.clone());
+        }
+    }
+    result
+}
+
+fn report(ctx: &mut actions::Context, result: CommandResult) {
+    match result {
         // TODO: we need a nicer way to handle status so we don't always erase previous (maybe
         // count repeated messages so it's visiable that You press the same key over and over?)
         Ok(..) => {} // ctx.app.status.clear(),
@@ -174,24 +458,186 @@ fn execute<'a>(
     };
 }
 
+/// Consumes key as an answer for pending confirmation, only 'y' confirms
+fn handle_confirmation<'a>(
+    key: crossterm::event::KeyEvent,
+    ctx: &mut actions::Context<'a>,
+    cmd_reactor: &mut CmdReactor<actions::Context<'a>>,
+) {
+    let confirmed = key.code == crossterm::event::KeyCode::Char('y');
+    match ctx.app.confirm.answer(confirmed) {
+        Some((name, args)) => {
+            ctx.app.status.clear();
+            let result = run_command(ctx, cmd_reactor, &name, args);
+            report(ctx, result);
+        }
+        None => ctx.app.status = "Cancelled".to_owned(),
+    }
+}
+
+/// Replaces command line with the one edited in external editor, original is kept on failure
+fn edit_command(ctx: &mut actions::Context, cmd: &str) {
+    match ctx.term.edit_text(cmd) {
+        Ok(Some(content)) => match app::edited_command(&content) {
+            Some(line) => ctx.app.mode_set(app::Mode::Command(Some(line))),
+            None => ctx.app.status = "Edited command is empty, keeping original".to_owned(),
+        },
+        Ok(None) => ctx.app.status = "Editor failed, keeping original command".to_owned(),
+        Err(e) => ctx.app.status = format!("Editing command failed with: {e}"),
+    }
+}
+
+/// Collects keys to explain until they form a whole binding, nothing gets executed
+fn handle_explain(key: crossterm::event::KeyEvent, ctx: &mut actions::Context) {
+    let Some(keys) = &mut ctx.app.explain else {
+        return;
+    };
+    keys.push(key);
+    let binding: String = keys.iter().map(|k| vim_key::to_vim_key(*k)).collect();
+    let longer = ctx.parser.is_prefix_keys(keys);
+    let action = ctx.parser.lookup(keys);
+    let done = action.is_some() || !longer;
+    ctx.app.status = match action {
+        None if longer => format!("Explain: {}...", binding),
+        Some(action) if longer => format!("{} → {} (or longer binding)", binding, action),
+        Some(action) => format!("{} → {}", binding, action),
+        None => format!("{} is unbound", binding),
+    };
+    if done {
+        ctx.app.explain = None;
+    }
+}
+
+fn handle_parsed_action<'a>(
+    parsed: ParsedAction<String>,
+    context: &mut actions::Context<'a>,
+    cmd_reactor: &mut CmdReactor<actions::Context<'a>>,
+) {
+    context
+        .app
+        .trace
+        .record("action", || format!("{:?}", parsed));
+    match parsed {
+        ParsedAction::Only(action) => {
+            execute(cmd_reactor, context, &action);
+        }
+        ParsedAction::Counted(count, action) => {
+            for _ in 0..context.app.repeat_count(count) {
+                // explicit count is not accelerated
+                context.app.scroll_accel.reset();
+                execute(cmd_reactor, context, &action);
+            }
+        }
+        ParsedAction::None | ParsedAction::Ambiguous(_) | ParsedAction::Partial => {}
+    }
+    // restart timeout on every key so pending keys are resolved after a pause
+    let pending = context.parser.is_pending();
+    context.app.restart_key_timeout(pending);
+    context.app.pending_count = context.parser.pending_count();
+}
+
+/// Runs on_select command once cursor settled on another commit, only in plain normal mode
+fn run_select_hook<'a>(
+    context: &mut actions::Context<'a>,
+    cmd_reactor: &mut CmdReactor<actions::Context<'a>>,
+    now: std::time::Instant,
+) {
+    if !matches!(context.app.mode(), app::Mode::Normal) || context.app.confirm.is_pending() {
+        return;
+    }
+    let sha = context.app.current_sha();
+    let Some(command) = context.app.on_select.tick(sha.as_deref(), now) else {
+        return;
+    };
+    execute(cmd_reactor, context, &command);
+    let sha = context.app.current_sha();
+    context.app.on_select.settle(sha.as_deref());
+}
+
+/// Dispatches keys of macro requested by last action, nested replays are limited in depth
+fn replay_macro<'a>(
+    context: &mut actions::Context<'a>,
+    cmd_reactor: &mut CmdReactor<actions::Context<'a>>,
+) {
+    let Some(keys) = context.app.macros.take_replay() else {
+        return;
+    };
+    if !context.app.macros.enter() {
+        context.app.status = "Macro recursion limit reached".to_owned();
+        return;
+    }
+    for key in keys {
+        // replay is scoped to normal mode
+        if !matches!(context.app.mode(), app::Mode::Normal) {
+            break;
+        }
+        handle_input_event(
+            InputEvent::Event(crossterm::event::Event::Key(key)),
+            context,
+            cmd_reactor,
+        );
+    }
+    context.app.macros.leave();
+}
+
 fn handle_input_event<'a>(
     event: InputEvent,
     context: &mut actions::Context<'a>,
     cmd_reactor: &mut CmdReactor<actions::Context<'a>>,
 ) {
-    #[allow(clippy::single_match)] // TODO: future use
+    // ticks come every 250ms, they'd flood the trace
+    if !matches!(event, InputEvent::Tick) {
+        context.app.trace.record("input", || format!("{:?}", event));
+    }
+    if let (InputEvent::Event(crossterm::event::Event::Key(_)), Some(autoplay)) =
+        (&event, &mut context.app.autoplay)
+    {
+        autoplay.input(context.app.clock.now());
+    }
     match event {
         input::InputEvent::Event(crossterm::event::Event::Key(e)) => match context.app.mode() {
-            app::Mode::Normal => match context.parser.handle_action(e) {
-                ParsedAction::Only(action) => {
-                    execute(cmd_reactor, context, &action);
+            app::Mode::Normal if context.app.confirm.is_pending() => {
+                handle_confirmation(e, context, cmd_reactor)
+            }
+            app::Mode::Normal if context.app.explain.is_some() => handle_explain(e, context),
+            app::Mode::Normal => {
+                // plain q stops recording, it cannot start a binding then
+                let stop_key = e.code == crossterm::event::KeyCode::Char('q')
+                    && e.modifiers == crossterm::event::KeyModifiers::NONE;
+                if stop_key && !context.parser.is_pending() {
+                    if let Some(register) = context.app.macros.stop_recording() {
+                        context.app.status = format!("Recorded @{}", register);
+                        return;
+                    }
                 }
-                ParsedAction::None => {
+                // Esc cancels half-entered binding or count
+                if e.code == crossterm::event::KeyCode::Esc
+                    && e.modifiers == crossterm::event::KeyModifiers::NONE
+                    && context.parser.is_pending()
+                {
+                    context.parser.reset_pending();
+                    context.app.key_deadline = None;
+                    context.app.pending_count = None;
+                    context.app.status = "Cancelled".to_owned();
+                    return;
+                }
+                context.app.macros.record(e);
+                let parsed = context.parser.handle_action(e);
+                if let ParsedAction::None = parsed {
                     context.app.status = format!("Not handled: {:?}", e);
                 }
-                ParsedAction::Ambiguous(_) => {}
-                ParsedAction::Partial => {}
-            },
+                handle_parsed_action(parsed, context, cmd_reactor);
+                replay_macro(context, cmd_reactor);
+            }
+            app::Mode::Jump => {
+                if let crossterm::event::KeyCode::Char(label) = e.code {
+                    if context.app.jump_to_label(label).is_none() {
+                        context.app.status = format!("No row labeled: {}", label);
+                    }
+                }
+                // any key ends jump, including <Esc>
+                context.app.mode_set(app::Mode::Normal);
+            }
             app::Mode::Command(_cmd) => {
                 let textarea = &mut context.app.textarea;
                 match e.into() {
@@ -206,11 +652,33 @@ fn handle_input_event<'a>(
                     } => {
                         let cmd = textarea.lines().last().expect("Command cannot be empty");
                         let cmd = cmd[1..].to_owned();
+                        context.app.history.push(&cmd);
                         context.app.status = format!("Command: {}", cmd);
                         context.app.mode_set(app::Mode::Normal);
 
                         execute(cmd_reactor, context, cmd.as_str());
                     }
+                    Input { key: Key::Up, .. } => {
+                        let line = textarea.lines().last().expect("Command cannot be empty");
+                        let prev = context.app.history.prev(&line[1..]).map(str::to_owned);
+                        if let Some(cmd) = prev {
+                            context.app.mode_set(app::Mode::Command(Some(cmd)));
+                        }
+                    }
+                    Input { key: Key::Down, .. } => {
+                        if let Some(cmd) = context.app.history.next().map(str::to_owned) {
+                            context.app.mode_set(app::Mode::Command(Some(cmd)));
+                        }
+                    }
+                    Input {
+                        key: Key::Char('e'),
+                        ctrl: true,
+                        ..
+                    } => {
+                        let cmd = textarea.lines().last().expect("Command cannot be empty");
+                        let cmd = cmd[1..].to_owned();
+                        edit_command(context, &cmd);
+                    }
                     input => {
                         if textarea.input(input)
                             && textarea
@@ -222,10 +690,33 @@ fn handle_input_event<'a>(
                             context.app.status = "Command mode quit".to_owned();
                             context.app.mode_set(app::Mode::Normal);
                         }
+                        context.app.update_query();
                     }
                 }
             }
         },
+        InputEvent::Event(crossterm::event::Event::Mouse(mouse)) => {
+            let click = matches!(
+                mouse.kind,
+                crossterm::event::MouseEventKind::Down(crossterm::event::MouseButton::Left)
+            );
+            let plain = matches!(context.app.mode(), app::Mode::Normal)
+                && !context.app.confirm.is_pending();
+            if !(click && plain) {
+                return;
+            }
+            if let Some(author) = context.app.author_at(mouse.column, mouse.row) {
+                let pattern = app::author_pattern(author);
+                let result = cmd_reactor.execute(context, "author_filter", vec![pattern]);
+                report(context, result);
+            }
+        }
+        InputEvent::Closed => context.app.should_quit = true,
+        InputEvent::Tick => {
+            let now = context.app.clock.now();
+            context.app.autoplay_tick(now);
+            run_select_hook(context, cmd_reactor, now);
+        }
         _ => {}
     }
 }
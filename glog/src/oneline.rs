@@ -0,0 +1,263 @@
+use crate::{
+    age::{Age, Buckets},
+    app::Entry,
+};
+
+/// Abbreviated length of hash when full hashes are not shown
+const SHORT_HASH_LEN: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RefKind {
+    /// ref pointed to by HEAD (or HEAD itself when detached)
+    Head,
+    Branch,
+    Remote,
+    Tag,
+}
+
+/// Content of a single list line in display order, independent of styling.
+#[derive(Debug, PartialEq)]
+pub enum Field<'a> {
+    /// marker shown when any entry is selected, true for the selected one
+    Selection(bool),
+    /// age badge when enabled, None for graph only rows
+    Age(Option<Age>),
+    /// initials of author (empty for graph only rows) and the author they come from
+    Initials(String, &'a str),
+    /// graph as produced by git, with ANSI colors
+    Graph(&'a str),
+    Hash(&'a str),
+    /// short name of command line ref via which the commit was reached, never empty
+    ReachedBy(&'a str),
+    /// never empty
+    Refs(Vec<(RefKind, &'a str)>),
+    Subject(&'a str),
+    AuthorDate(String),
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    pub full_hash: bool,
+    /// some entry of the list is selected so all lines get selection column
+    pub has_selection: bool,
+    /// age buckets and current unix time when age badge is shown
+    pub age: Option<(Buckets, i64)>,
+    pub reached_by: bool,
+    pub initials: bool,
+}
+
+/// Returns upper-cased first letters of the first and the last word of name, single word gives
+/// one letter only
+pub fn initials(name: &str) -> String {
+    let mut words = name.split_whitespace();
+    let first = words.next();
+    [first, words.last()]
+        .into_iter()
+        .flatten()
+        .filter_map(|word| word.chars().next())
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// Returns fields of entry as shown in the list. Graph only rows (no hash) have just the graph.
+pub fn fields<'a>(entry: &'a Entry, options: Options) -> Vec<Field<'a>> {
+    let git = &entry.git;
+    let mut fields = Vec::new();
+    if options.has_selection {
+        fields.push(Field::Selection(entry.selected()));
+    }
+    if let Some((buckets, now)) = options.age {
+        let age = git.timestamp.map(|t| buckets.age(t, now));
+        fields.push(Field::Age(age));
+    }
+    if options.initials {
+        fields.push(Field::Initials(initials(&git.author), &git.author));
+    }
+    fields.push(Field::Graph(&git.graph));
+    if git.hash.is_empty() {
+        return fields;
+    }
+
+    let hash = if options.full_hash {
+        &git.hash
+    } else {
+        // hashes are ASCII
+        &git.hash[..std::cmp::min(SHORT_HASH_LEN, git.hash.len())]
+    };
+    fields.push(Field::Hash(hash));
+
+    let reached_by = git.reached_by_short();
+    if options.reached_by && !reached_by.is_empty() {
+        fields.push(Field::ReachedBy(reached_by));
+    }
+
+    if let Some(refs) = &git.refs {
+        let refs: Vec<_> = refs
+            .head
+            .iter()
+            .map(|r| (RefKind::Head, r.as_str()))
+            .chain(refs.heads.iter().map(|r| (RefKind::Branch, r.as_str())))
+            .chain(
+                refs.remotes
+                    .iter()
+                    .filter(|r| r.as_str() != "origin/HEAD")
+                    .map(|r| (RefKind::Remote, r.as_str())),
+            )
+            .chain(refs.tags.iter().map(|r| (RefKind::Tag, r.as_str())))
+            .collect();
+        if !refs.is_empty() {
+            fields.push(Field::Refs(refs));
+        }
+    }
+    fields.push(Field::Subject(&git.subject));
+    fields.push(Field::AuthorDate(git.author_and_date()));
+    fields
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fields, initials, Field, Options, RefKind};
+    use crate::{
+        age::{Age, Buckets},
+        app::Entry,
+    };
+
+    const TIMESTAMP: i64 = 1_700_000_000;
+
+    fn entry(hash: &str, refs: &str) -> Entry {
+        Entry::new(git::LogEntry {
+            graph: "* ".to_owned(),
+            hash: hash.to_owned(),
+            subject: "subject".to_owned(),
+            author: "author".to_owned(),
+            date: "date".to_owned(),
+            timestamp: (!hash.is_empty()).then_some(TIMESTAMP),
+            refs: git::RefNames::from(refs),
+            ..Default::default()
+        })
+    }
+
+    const HASH: &str = "d401a57c0ffee0d401a57c0ffee0d401a57c0ffe";
+
+    #[test]
+    fn without_refs() {
+        let entry = entry(HASH, "");
+        assert_eq!(
+            fields(&entry, Options::default()),
+            [
+                Field::Graph("* "),
+                Field::Hash("d401a57c"),
+                Field::Subject("subject"),
+                Field::AuthorDate("(author, date)".to_owned()),
+            ]
+        );
+        let options = Options {
+            full_hash: true,
+            has_selection: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            fields(&entry, options)[..3],
+            [
+                Field::Selection(false),
+                Field::Graph("* "),
+                Field::Hash(HASH)
+            ]
+        );
+    }
+
+    #[test]
+    fn with_refs() {
+        let entry = entry(
+            HASH,
+            "HEAD -> refs/heads/main, refs/heads/other, refs/remotes/origin/HEAD, \
+             refs/remotes/origin/main, tag: refs/tags/v1",
+        );
+        assert_eq!(
+            fields(&entry, Options::default())[2],
+            Field::Refs(vec![
+                (RefKind::Head, "main"),
+                (RefKind::Branch, "other"),
+                (RefKind::Remote, "origin/main"),
+                (RefKind::Tag, "v1"),
+            ])
+        );
+
+        // only hidden refs
+        let entry = self::entry(HASH, "refs/remotes/origin/HEAD");
+        assert!(!fields(&entry, Options::default())
+            .iter()
+            .any(|f| matches!(f, Field::Refs(_))));
+    }
+
+    #[test]
+    fn empty_hash() {
+        let entry = entry("", "");
+        assert_eq!(fields(&entry, Options::default()), [Field::Graph("* ")]);
+        let options = Options {
+            has_selection: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            fields(&entry, options),
+            [Field::Selection(false), Field::Graph("* ")]
+        );
+    }
+
+    #[test]
+    fn reached_by() {
+        let options = Options {
+            reached_by: true,
+            ..Default::default()
+        };
+        let mut main = entry(HASH, "");
+        main.git.reached_by = "refs/heads/main".to_owned();
+        let mut head = entry(HASH, "");
+        head.git.reached_by = "HEAD".to_owned();
+        let unknown = entry(HASH, "");
+
+        assert_eq!(fields(&main, options)[2], Field::ReachedBy("main"));
+        assert_eq!(fields(&head, options)[2], Field::ReachedBy("HEAD"));
+        assert_eq!(fields(&unknown, options)[2], Field::Subject("subject"));
+        // off by default
+        assert_eq!(
+            fields(&main, Options::default())[1..3],
+            [Field::Hash("d401a57c"), Field::Subject("subject")]
+        );
+    }
+
+    #[test]
+    fn author_initials() {
+        assert_eq!(initials("Junio C Hamano"), "JH");
+        assert_eq!(initials("Linus Torvalds"), "LT");
+        assert_eq!(initials("  dependabot[bot] "), "D");
+        assert_eq!(initials("Łukasz ørsted"), "ŁØ");
+        assert_eq!(initials(""), "");
+        assert_eq!(initials("   "), "");
+
+        let options = Options {
+            initials: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            fields(&entry(HASH, ""), options)[0],
+            Field::Initials("A".to_owned(), "author")
+        );
+    }
+
+    #[test]
+    fn age() {
+        let options = Options {
+            age: Some((Buckets::default(), TIMESTAMP + 60)),
+            ..Default::default()
+        };
+        assert_eq!(
+            fields(&entry(HASH, ""), options)[..2],
+            [Field::Age(Some(Age::Today)), Field::Graph("* ")]
+        );
+        assert_eq!(
+            fields(&entry("", ""), options),
+            [Field::Age(None), Field::Graph("* ")]
+        );
+    }
+}
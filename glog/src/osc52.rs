@@ -0,0 +1,65 @@
+/// Copying through the terminal with OSC 52 escape sequence, works also over SSH.
+/// Terminals cap the sequence length (e.g. hterm at 100000 bytes), longer ones are refused.
+pub const MAX_SEQUENCE_LEN: usize = 100_000;
+
+const PREFIX: &str = "\x1b]52;c;";
+const TERMINATOR: &str = "\x07";
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let triple = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (triple >> (18 - 6 * i)) & 0x3f;
+                encoded.push(ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Returns escape sequence setting clipboard to text
+pub fn sequence(text: &str) -> Result<String, String> {
+    let payload = base64(text.as_bytes());
+    let len = PREFIX.len() + payload.len() + TERMINATOR.len();
+    if len > MAX_SEQUENCE_LEN {
+        return Err(format!(
+            "Text too long for OSC 52 ({} > {} bytes)",
+            len, MAX_SEQUENCE_LEN
+        ));
+    }
+    Ok(format!("{PREFIX}{payload}{TERMINATOR}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{base64, sequence, MAX_SEQUENCE_LEN};
+
+    #[test]
+    fn encoding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+        assert_eq!(base64("d401a57 ż".as_bytes()), "ZDQwMWE1NyDFvA==");
+    }
+
+    #[test]
+    fn payload_cap() {
+        assert_eq!(sequence("foo").unwrap(), "\x1b]52;c;Zm9v\x07");
+        // 3 bytes of text take 4 bytes of payload
+        let fits = (MAX_SEQUENCE_LEN - 8) / 4 * 3;
+        assert!(sequence(&"x".repeat(fits)).is_ok());
+        assert!(sequence(&"x".repeat(fits + 1)).is_err());
+    }
+}
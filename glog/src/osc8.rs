@@ -0,0 +1,28 @@
+/// Hyperlinks in terminal output with OSC 8 escape sequence, supporting terminals show text and
+/// open URL on click, others usually show just the text.
+const START: &str = "\x1b]8;;";
+const TERMINATOR: &str = "\x1b\\";
+
+/// Returns text linked to URL. Control characters would end the sequence early so they are
+/// dropped from URL.
+pub fn hyperlink(url: &str, text: &str) -> String {
+    let url: String = url.chars().filter(|c| !c.is_control()).collect();
+    format!("{START}{url}{TERMINATOR}{text}{START}{TERMINATOR}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::hyperlink;
+
+    #[test]
+    fn sequence() {
+        assert_eq!(
+            hyperlink("https://example.com/commit/d401a57", "d401a57"),
+            "\x1b]8;;https://example.com/commit/d401a57\x1b\\d401a57\x1b]8;;\x1b\\"
+        );
+        assert_eq!(
+            hyperlink("https://e.com/\x1b\x07x", "x"),
+            "\x1b]8;;https://e.com/x\x1b\\x\x1b]8;;\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+/// Cursor has to stay on a commit this long before the hook runs
+pub const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Command run when cursor settles on another commit (`:set on_select=...`), e.g. to let an
+/// external tool follow the current sha. Cursor is checked on input ticks so fast scrolling
+/// runs the hook only once it stops.
+#[derive(Debug, Default)]
+pub struct SelectHook {
+    pub command: Option<String>,
+    /// sha seen on last tick
+    last: Option<String>,
+    /// when cursor moved to `last`, None once the hook ran for it
+    changed: Option<Instant>,
+}
+
+impl SelectHook {
+    /// Returns command to run when the cursor stayed on a new commit for DEBOUNCE
+    pub fn tick(&mut self, sha: Option<&str>, now: Instant) -> Option<String> {
+        if self.last.as_deref() != sha {
+            self.last = sha.map(str::to_owned);
+            self.changed = sha.map(|_| now);
+            return None;
+        }
+        let changed = self.changed?;
+        if now.saturating_duration_since(changed) < DEBOUNCE {
+            return None;
+        }
+        self.changed = None;
+        self.command.clone()
+    }
+
+    /// Takes sha as already handled so moves done by the hook itself don't trigger it again
+    pub fn settle(&mut self, sha: Option<&str>) {
+        self.last = sha.map(str::to_owned);
+        self.changed = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::SelectHook;
+
+    #[test]
+    fn runs_once_cursor_settles() {
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        let mut hook = SelectHook {
+            command: Some("exec touch %0".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(hook.tick(Some("a"), ms(0)), None);
+        // fast scroll
+        assert_eq!(hook.tick(Some("b"), ms(250)), None);
+        assert_eq!(hook.tick(Some("c"), ms(500)), None);
+        assert_eq!(hook.tick(Some("c"), ms(600)), None);
+        assert_eq!(hook.tick(Some("c"), ms(750)), hook.command.clone());
+        // only once per commit
+        assert_eq!(hook.tick(Some("c"), ms(1000)), None);
+        // empty list
+        assert_eq!(hook.tick(None, ms(1250)), None);
+        assert_eq!(hook.tick(None, ms(1500)), None);
+    }
+
+    #[test]
+    fn disabled_and_settled() {
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        let mut hook = SelectHook::default();
+        hook.tick(Some("a"), ms(0));
+        assert_eq!(hook.tick(Some("a"), ms(250)), None);
+
+        hook.command = Some("echo".to_owned());
+        hook.tick(Some("b"), ms(500));
+        // hook command moved the cursor
+        hook.settle(Some("c"));
+        assert_eq!(hook.tick(Some("c"), ms(750)), None);
+        assert_eq!(hook.tick(Some("c"), ms(1000)), None);
+    }
+}
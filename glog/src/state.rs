@@ -0,0 +1,123 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::history::MAX_HISTORY;
+
+/// State kept between sessions per repository: command history and marks.
+/// Stored as lines of `history <command>` and `mark <register> <sha>`, unknown lines are ignored.
+#[derive(Debug, Default, PartialEq)]
+pub struct State {
+    pub history: Vec<String>,
+    pub marks: BTreeMap<char, String>,
+}
+
+impl State {
+    pub fn parse(text: &str) -> Self {
+        let mut state = State::default();
+        for line in text.lines() {
+            if let Some(command) = line.strip_prefix("history ") {
+                if !command.trim().is_empty() {
+                    state.history.push(command.to_owned());
+                }
+            } else if let Some(mark) = line.strip_prefix("mark ") {
+                let mut chars = mark.chars();
+                if let (Some(register), Some(' ')) = (chars.next(), chars.next()) {
+                    state.marks.insert(register, chars.as_str().to_owned());
+                }
+            }
+        }
+        state
+    }
+
+    /// Serializes state, only last MAX_HISTORY commands are kept
+    pub fn serialize(&self) -> String {
+        let history = &self.history[self.history.len().saturating_sub(MAX_HISTORY)..];
+        let mut text = String::new();
+        for (register, sha) in &self.marks {
+            text.push_str(&format!("mark {} {}\n", register, sha));
+        }
+        for command in history {
+            text.push_str(&format!("history {}\n", command));
+        }
+        text
+    }
+
+    /// Missing or unreadable state is just empty
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(text) => State::parse(&text),
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    log::warn!("Reading state {:?} failed: {}", path, e);
+                }
+                State::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.serialize())
+    }
+}
+
+/// Returns state file of repository in $XDG_STATE_HOME/glog (~/.local/state/glog by default).
+/// File is named after repository path with `/` replaced by `%`, the same as vim does for undo
+/// files.
+pub fn state_path(repository: &Path) -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_STATE_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".local/state")))?;
+    let name = repository.to_string_lossy().replace('/', "%");
+    Some(dir.join("glog").join(name))
+}
+
+#[cfg(test)]
+mod test {
+    use super::State;
+    use crate::history::MAX_HISTORY;
+
+    #[test]
+    fn roundtrip() {
+        let state = State {
+            history: vec!["reload HEAD".to_owned(), "exec git show %0".to_owned()],
+            marks: [('a', "d401a57".to_owned()), ('b', "c0ffee".to_owned())].into(),
+        };
+        let text = state.serialize();
+        assert_eq!(
+            text,
+            "mark a d401a57\nmark b c0ffee\nhistory reload HEAD\nhistory exec git show %0\n"
+        );
+        assert_eq!(State::parse(&text), state);
+    }
+
+    #[test]
+    fn bounded_and_forgiving() {
+        let state = State {
+            history: (0..MAX_HISTORY + 1).map(|i| i.to_string()).collect(),
+            ..Default::default()
+        };
+        let parsed = State::parse(&state.serialize());
+        assert_eq!(parsed.history.len(), MAX_HISTORY);
+        assert_eq!(parsed.history[0], "1");
+
+        let parsed = State::parse("garbage\nmark x\nmark ab c\nhistory \nmark c abc\n");
+        assert!(parsed.history.is_empty());
+        assert_eq!(
+            parsed.marks.into_iter().collect::<Vec<_>>(),
+            [('c', "abc".to_owned())]
+        );
+    }
+
+    #[test]
+    fn missing_file() {
+        let path = std::env::temp_dir().join("glog-test-missing-state");
+        assert_eq!(State::load(&path), State::default());
+    }
+}
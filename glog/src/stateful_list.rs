@@ -5,34 +5,94 @@ pub trait Selectable {
     fn toggle_selected(&mut self);
 }
 
-pub struct StatefulList<T>
-{
+pub struct StatefulList<T> {
     list: Vec<T>,
     state: scrollview::StatefulPosition,
-    /// list of elements currently selected
+    /// elements currently selected in order of selection, front is the anchor
     selections: VecDeque<usize>,
+    /// oldest selections are dropped when exceeded, None means unlimited
+    max_selections: Option<usize>,
+    /// sorted indexes of elements shown when filtered, None shows all elements.
+    /// Positions used by this API are always indexes to all elements, scroll state works on
+    /// visible ones.
+    visible: Option<Vec<usize>>,
 }
 
-impl<T> StatefulList<T>
-{
+impl<T> StatefulList<T> {
     pub fn new() -> Self {
         Self {
             list: Vec::new(),
             state: scrollview::StatefulPosition::default(),
             selections: VecDeque::default(),
+            max_selections: Some(1),
+            visible: None,
         }
     }
 
+    /// Clears all data, filter stays enabled (but empty) when it was set
     pub fn reset(&mut self) {
         self.list = Vec::new();
         // TODO: offset should be part of API one day
         self.state.reset(5, 0);
         self.selections.clear();
+        if let Some(visible) = &mut self.visible {
+            visible.clear();
+        }
     }
 
     pub fn push(&mut self, mut data: Vec<T>) {
         self.list.append(&mut data);
-        self.state.length_extended(self.list.len());
+        self.state.length_extended(self.visible_len());
+    }
+
+    /// Shows only elements with given (sorted) indexes or all of them for None.
+    /// Cursor stays on current element or moves to the closest visible one above it.
+    pub fn set_filter(&mut self, visible: Option<Vec<usize>>) {
+        let current = self.current_position();
+        self.visible = visible;
+        self.state.reset(5, self.visible_len());
+        self.state.select(self.visible_index(current));
+    }
+
+    /// Adds indexes (greater than already visible ones) of newly pushed elements to the filter
+    pub fn extend_filter(&mut self, indexes: impl IntoIterator<Item = usize>) {
+        if let Some(visible) = &mut self.visible {
+            visible.extend(indexes);
+            self.state.length_extended(visible.len());
+        }
+    }
+
+    pub fn is_visible(&self, index: usize) -> bool {
+        match &self.visible {
+            Some(visible) => visible.binary_search(&index).is_ok(),
+            None => index < self.list.len(),
+        }
+    }
+
+    fn visible_len(&self) -> usize {
+        self.visible.as_ref().map_or(self.list.len(), Vec::len)
+    }
+
+    /// Maps index of visible element to index of all elements
+    fn raw_index(&self, visible_index: usize) -> usize {
+        match &self.visible {
+            Some(visible) => visible
+                .get(visible_index)
+                .copied()
+                .unwrap_or(self.list.len()),
+            None => visible_index,
+        }
+    }
+
+    /// Maps index of all elements to visible one, hidden element maps to closest visible above
+    fn visible_index(&self, index: usize) -> usize {
+        match &self.visible {
+            Some(visible) => match visible.binary_search(&index) {
+                Ok(i) => i,
+                Err(i) => i.saturating_sub(1),
+            },
+            None => index,
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -55,8 +115,16 @@ impl<T> StatefulList<T>
         self.state.end()
     }
 
+    /// Moves cursor by delta visible elements, returns false when it stopped at end of the list
+    pub fn scroll_by(&mut self, delta: isize) -> bool {
+        let last = self.visible_len().saturating_sub(1) as isize;
+        let target = self.state.position() as isize + delta;
+        self.state.select(target.clamp(0, last) as usize);
+        (0..=last).contains(&target)
+    }
+
     pub fn scroll_to_position(&mut self, pos: usize) {
-        self.state.select(pos);
+        self.state.select(self.visible_index(pos));
     }
 
     pub fn has_selected(&self) -> bool {
@@ -71,55 +139,84 @@ impl<T> StatefulList<T>
         None
     }
 
+    /// Iterates over selected elements in order of selection, anchor first
+    pub fn iter_selected(&self) -> impl Iterator<Item = &T> {
+        self.selections.iter().map(|&idx| &self.list[idx])
+    }
+
+    /// Returns whether there are selected elements (above, below) current view.
+    pub fn selections_outside_view(&self) -> (bool, bool) {
+        let view = self.view();
+        if view.start >= view.end {
+            return (false, false);
+        }
+        let start = self.raw_index(view.start);
+        let end = self.raw_index(view.end - 1) + 1;
+        let above = self.selections.iter().any(|&idx| idx < start);
+        let below = self.selections.iter().any(|&idx| idx >= end);
+        (above, below)
+    }
+
+    pub fn set_scroll_mode(&mut self, mode: scrollview::ScrollMode) {
+        self.state.set_mode(mode)
+    }
+
     pub fn set_view_height(&mut self, height: u16) {
         self.state.set_height(height as usize)
     }
 
+    /// Returns currently visible slice bounds, constrained by list length.
+    pub fn view(&self) -> scrollview::View {
+        self.state.get_view()
+    }
+
+    /// Returns count of currently visible elements.
+    pub fn view_len(&self) -> usize {
+        let view = self.view();
+        view.end - view.start
+    }
+
     // Returns a position and view as iterator to slice of data.
     // Note: Position is returned with view to avoid using out-of-date position with this view.
     pub fn iter_view(&self) -> (usize, impl Iterator<Item = &T>) {
-        let view = self.state.get_view();
-        let iter = self
-            .list
-            .iter()
-            // take slice from iter [start..end]
-            .take(view.end)
-            .skip(view.start);
+        let view = self.view();
+        let iter = (view.start..view.end).map(|i| &self.list[self.raw_index(i)]);
         (view.pos, iter)
     }
 
+    /// Returns index of element shown in given row of current view
+    pub fn view_row_index(&self, row: usize) -> Option<usize> {
+        (row < self.view_len()).then(|| self.raw_index(self.view().start + row))
+    }
+
     pub fn iter_all(&self) -> Iter<'_, T> {
         self.list.iter()
     }
 
     pub fn current(&self) -> Option<&T> {
-        let selected = self.state.position();
-        self.list.get(selected)
+        self.list.get(self.current_position())
     }
 
     pub fn current_position(&self) -> usize {
-        self.state.position()
+        self.raw_index(self.state.position())
     }
 
     fn current_mut(&mut self) -> Option<&mut T> {
-        let selected = self.state.position();
+        let selected = self.current_position();
         self.list.get_mut(selected)
     }
 
     pub fn toggle_select_for_current(&mut self) -> Option<()>
     where
-        T: Selectable
+        T: Selectable,
     {
-        let pos = self.state.position();
+        let pos = self.current_position();
 
         self.current_mut()?.toggle_selected();
 
         if self.current()?.selected() {
-            if !self.selections.is_empty() {
-                let x = self.selections.pop_front().unwrap();
-                self.list[x].toggle_selected()
-            }
             self.selections.push_back(pos);
+            self.evict_selections();
         } else {
             self.selections.retain(|e| *e != pos);
         }
@@ -127,7 +224,316 @@ impl<T> StatefulList<T>
         Some(())
     }
 
+    /// Limits number of selections, oldest are deselected first (so anchor moves to next one).
+    /// Limit of 0 is treated as 1.
+    pub fn set_max_selections(&mut self, max: Option<usize>)
+    where
+        T: Selectable,
+    {
+        self.max_selections = max.map(|max| std::cmp::max(max, 1));
+        self.evict_selections();
+    }
+
+    fn evict_selections(&mut self)
+    where
+        T: Selectable,
+    {
+        let Some(max) = self.max_selections else {
+            return;
+        };
+        while self.selections.len() > max {
+            if let Some(idx) = self.selections.pop_front() {
+                self.list[idx].toggle_selected();
+            }
+        }
+    }
+
     pub fn center(&mut self) {
         self.state.center()
     }
+
+    pub fn align_top(&mut self) {
+        self.state.align_top()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Selectable, StatefulList};
+
+    #[derive(Debug, PartialEq)]
+    struct Item {
+        id: usize,
+        selected: bool,
+    }
+
+    impl Selectable for Item {
+        fn selected(&self) -> bool {
+            self.selected
+        }
+
+        fn toggle_selected(&mut self) {
+            self.selected ^= true
+        }
+    }
+
+    fn items(range: std::ops::Range<usize>) -> Vec<Item> {
+        range
+            .map(|id| Item {
+                id,
+                selected: false,
+            })
+            .collect()
+    }
+
+    /// Creates a list the same way App does: height is known before reset and data comes later.
+    fn list_with_height(height: u16) -> StatefulList<Item> {
+        let mut list = StatefulList::new();
+        list.set_view_height(height);
+        list.reset();
+        list
+    }
+
+    macro_rules! assert_view {
+        ($list:ident, $pos:expr, $ids:expr) => {
+            let (pos, iter) = $list.iter_view();
+            assert_eq!(pos, $pos);
+            assert_eq!(iter.map(|e| e.id).collect::<Vec<_>>(), $ids);
+        };
+    }
+
+    #[test]
+    fn push_in_chunks() {
+        let mut list = list_with_height(10);
+        assert_view!(list, 0, Vec::<usize>::new());
+        assert!(list.current().is_none());
+
+        list.push(items(0..3));
+        assert_eq!(list.len(), 3);
+        assert_view!(list, 0, vec![0, 1, 2]);
+
+        list.push(items(3..25));
+        assert_eq!(list.len(), 25);
+        assert_view!(list, 0, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn scroll_and_iter_view() {
+        let mut list = list_with_height(10);
+        list.push(items(0..25));
+
+        list.scroll_next(3);
+        assert_eq!(list.current_position(), 3);
+        assert_view!(list, 3, (0..10).collect::<Vec<_>>());
+
+        // scrolling keeps offset below cursor
+        list.scroll_next(5);
+        assert_eq!(list.current().map(|e| e.id), Some(8));
+        assert_view!(list, 4, (4..14).collect::<Vec<_>>());
+
+        list.scroll_end();
+        assert_eq!(list.current().map(|e| e.id), Some(24));
+        assert_view!(list, 9, (15..25).collect::<Vec<_>>());
+
+        // data pushed while scrolled does not move the view
+        list.push(items(25..30));
+        assert_view!(list, 9, (15..25).collect::<Vec<_>>());
+
+        list.scroll_start();
+        assert_view!(list, 0, (0..10).collect::<Vec<_>>());
+
+        list.scroll_to_position(20);
+        assert_eq!(list.current().map(|e| e.id), Some(20));
+    }
+
+    #[test]
+    fn view() {
+        let mut list = list_with_height(10);
+        assert_eq!(list.view_len(), 0);
+
+        list.push(items(0..3));
+        assert_eq!(list.view_len(), 3);
+
+        list.push(items(3..25));
+        list.scroll_end();
+        let view = list.view();
+        assert_eq!((view.pos, view.start, view.end), (9, 15, 25));
+        assert_eq!(list.view_len(), 10);
+    }
+
+    #[test]
+    fn scroll_short_list() {
+        let mut list = list_with_height(10);
+        list.push(items(0..3));
+
+        list.scroll_next(10);
+        assert_view!(list, 2, vec![0, 1, 2]);
+        list.scroll_prev(10);
+        assert_view!(list, 0, vec![0, 1, 2]);
+        list.scroll_end();
+        assert_view!(list, 2, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn selection_anchor() {
+        let mut list = list_with_height(10);
+        list.push(items(0..25));
+        assert!(!list.has_selected());
+        assert!(list.selected0().is_none());
+
+        // first selection becomes the anchor
+        list.scroll_to_position(2);
+        list.toggle_select_for_current();
+        assert!(list.has_selected());
+        assert_eq!(list.selected0().map(|e| e.id), Some(2));
+        assert!(list.current().unwrap().selected());
+
+        // selecting another element moves the anchor and deselects the old one
+        list.scroll_to_position(5);
+        list.toggle_select_for_current();
+        assert_eq!(list.selected0().map(|e| e.id), Some(5));
+        assert!(!list.iter_all().nth(2).unwrap().selected());
+        assert_eq!(list.iter_all().filter(|e| e.selected()).count(), 1);
+
+        // toggling the anchor again clears selection
+        list.toggle_select_for_current();
+        assert!(!list.has_selected());
+        assert!(list.selected0().is_none());
+        assert_eq!(list.iter_all().filter(|e| e.selected()).count(), 0);
+    }
+
+    fn selected_ids(list: &StatefulList<Item>) -> Vec<usize> {
+        list.iter_all()
+            .filter(|e| e.selected())
+            .map(|e| e.id)
+            .collect()
+    }
+
+    fn select(list: &mut StatefulList<Item>, ids: &[usize]) {
+        for id in ids {
+            list.scroll_to_position(*id);
+            list.toggle_select_for_current();
+        }
+    }
+
+    #[test]
+    fn selections_eviction() {
+        let mut list = list_with_height(10);
+        list.push(items(0..25));
+        list.set_max_selections(Some(3));
+
+        select(&mut list, &[7, 2, 9]);
+        assert_eq!(list.selected0().map(|e| e.id), Some(7));
+        assert_eq!(selected_ids(&list), [2, 7, 9]);
+        assert_eq!(
+            list.iter_selected().map(|e| e.id).collect::<Vec<_>>(),
+            [7, 2, 9]
+        );
+
+        // oldest selection is dropped, next oldest becomes the anchor
+        select(&mut list, &[4]);
+        assert_eq!(list.selected0().map(|e| e.id), Some(2));
+        assert_eq!(selected_ids(&list), [2, 4, 9]);
+
+        // deselecting other than anchor keeps it
+        select(&mut list, &[9]);
+        assert_eq!(list.selected0().map(|e| e.id), Some(2));
+        select(&mut list, &[1, 3]);
+        assert_eq!(list.selected0().map(|e| e.id), Some(4));
+        assert_eq!(selected_ids(&list), [1, 3, 4]);
+
+        // lowering the limit evicts immediately
+        list.set_max_selections(Some(1));
+        assert_eq!(selected_ids(&list), [3]);
+        assert_eq!(list.selected0().map(|e| e.id), Some(3));
+
+        list.set_max_selections(None);
+        select(&mut list, &(10..20).collect::<Vec<_>>());
+        assert_eq!(list.selected0().map(|e| e.id), Some(3));
+        assert_eq!(selected_ids(&list).len(), 11);
+    }
+
+    #[test]
+    fn selections_outside_view() {
+        let mut list = list_with_height(10);
+        list.push(items(0..40));
+        assert_eq!(list.selections_outside_view(), (false, false));
+
+        list.scroll_to_position(2);
+        list.toggle_select_for_current();
+        assert_eq!(list.selections_outside_view(), (false, false));
+
+        list.scroll_to_position(30);
+        assert_eq!(list.selections_outside_view(), (true, false));
+
+        list.toggle_select_for_current();
+        list.scroll_start();
+        assert_eq!(list.selections_outside_view(), (false, true));
+    }
+
+    #[test]
+    fn filter() {
+        let mut list = list_with_height(3);
+        list.push(items(0..10));
+        list.scroll_to_position(5);
+
+        // cursor on hidden element moves to closest visible above
+        list.set_filter(Some(vec![0, 2, 4, 6, 8]));
+        assert_eq!(list.current_position(), 4);
+        assert!(list.is_visible(6));
+        assert!(!list.is_visible(5));
+
+        list.scroll_start();
+        assert_view!(list, 0, vec![0, 2, 4]);
+        assert_eq!(list.view_row_index(1), Some(2));
+        assert_eq!(list.view_row_index(3), None);
+        list.scroll_next(1);
+        assert_eq!(list.current().map(|e| e.id), Some(2));
+        list.scroll_end();
+        assert_eq!(list.current().map(|e| e.id), Some(8));
+
+        list.push(items(10..12));
+        list.extend_filter([11]);
+        list.scroll_end();
+        assert_eq!(list.current().map(|e| e.id), Some(11));
+
+        // filter survives reset, but is empty until extended
+        list.reset();
+        list.push(items(0..3));
+        assert!(list.current().is_none());
+        list.extend_filter([1]);
+        assert_view!(list, 0, vec![1]);
+
+        list.set_filter(None);
+        assert_eq!(list.current_position(), 1);
+        list.scroll_start();
+        assert_view!(list, 0, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn toggle_select_on_empty_list() {
+        let mut list = list_with_height(10);
+        assert_eq!(list.toggle_select_for_current(), None);
+        assert!(!list.has_selected());
+    }
+
+    #[test]
+    fn reset_clears_everything() {
+        let mut list = list_with_height(10);
+        list.push(items(0..25));
+        list.scroll_to_position(12);
+        list.toggle_select_for_current();
+
+        list.reset();
+        assert_eq!(list.len(), 0);
+        assert!(!list.has_selected());
+        assert!(list.current().is_none());
+        assert_eq!(list.current_position(), 0);
+        assert_view!(list, 0, Vec::<usize>::new());
+
+        // list is usable again after reset
+        list.push(items(0..25));
+        assert_view!(list, 0, (0..10).collect::<Vec<_>>());
+        assert!(list.iter_all().all(|e| !e.selected()));
+    }
 }
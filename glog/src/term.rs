@@ -1,8 +1,10 @@
 use crate::utils::*;
 use std::io::Write;
+use std::process::ExitStatus;
 use std::{env, io};
 
 use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -11,33 +13,38 @@ use ratatui::{backend::CrosstermBackend, Terminal};
 
 pub struct Term {
     pub terminal: Terminal<CrosstermBackend<io::Stdout>>,
+    /// mouse events are reported, terminal's own text selection usually needs Shift then
+    mouse: bool,
 }
 
 impl Term {
-    pub fn new() -> Result<Self, io::Error> {
+    pub fn new(mouse: bool) -> Result<Self, io::Error> {
         let stdout = io::stdout();
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
-        let mut term = Term { terminal };
+        let mut term = Term { terminal, mouse };
         term.enter()?;
         Ok(term)
     }
 
-    pub fn call<F>(&mut self, func: F) -> Result<(), io::Error>
+    pub fn call<F, R>(&mut self, func: F) -> Result<R, io::Error>
     where
-        F: FnOnce(),
+        F: FnOnce() -> R,
     {
         self.restore()?;
-        func();
+        let result = func();
         self.enter()?;
         self.clear();
-        Ok(())
+        Ok(result)
     }
 
-    pub fn call_external(&mut self, mut command: std::process::Command) -> Result<(), io::Error> {
+    pub fn call_external(
+        &mut self,
+        mut command: std::process::Command,
+    ) -> Result<ExitStatus, io::Error> {
         self.call(|| {
             let result = command.status();
-            match result {
+            match &result {
                 Ok(exit_code) => {
                     if !exit_code.success() {
                         warn!(
@@ -48,7 +55,32 @@ impl Term {
                 }
                 Err(err) => warn!("Command {:#?} finished with error: {}", command, err),
             };
-        })
+            result
+        })?
+    }
+
+    /// Opens $VISUAL or $EDITOR (vi by default) with text and returns edited content.
+    /// None is returned when editor exits with failure.
+    pub fn edit_text(&mut self, text: &str) -> Result<Option<String>, io::Error> {
+        let editor = env::var("VISUAL")
+            .or_else(|_| env::var("EDITOR"))
+            .unwrap_or("vi".into());
+        let path = env::temp_dir().join(format!("glog-command-{}.txt", std::process::id()));
+        std::fs::write(&path, text)?;
+        // editor may have arguments, the same as git does it's run via shell
+        let mut command = std::process::Command::new("sh");
+        command
+            .args(["-c", &format!("{} \"$@\"", editor), "editor"])
+            .arg(&path);
+        let status = self.call_external(command);
+        let content = std::fs::read_to_string(&path);
+        if let Err(err) = std::fs::remove_file(&path) {
+            warn!("Removing {:?} failed: {}", path, err);
+        }
+        if !status?.success() {
+            return Ok(None);
+        }
+        content.map(Some)
     }
 
     pub fn clear(&mut self) {
@@ -57,23 +89,29 @@ impl Term {
         }
     }
 
+    /// Writes bytes directly to terminal, bypassing the screen buffer
+    pub fn write_raw(&mut self, data: &str) -> Result<(), io::Error> {
+        let out = self.terminal.backend_mut();
+        out.write_all(data.as_bytes())?;
+        out.flush()
+    }
+
     fn enter(&mut self) -> Result<(), io::Error> {
         enable_raw_mode()?;
-        execute!(
-            io::stdout(),
-            EnterAlternateScreen,
-            // EnableMouseCapture
-        )
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        if self.mouse {
+            execute!(io::stdout(), EnableMouseCapture)?;
+        }
+        Ok(())
     }
 
     fn restore(&mut self) -> Result<(), io::Error> {
         // restore terminal
         disable_raw_mode()?;
-        execute!(
-            io::stdout(),
-            LeaveAlternateScreen,
-            // DisableMouseCapture
-        )?;
+        if self.mouse {
+            execute!(io::stdout(), DisableMouseCapture)?;
+        }
+        execute!(io::stdout(), LeaveAlternateScreen)?;
         self.terminal.show_cursor()?;
         Ok(())
     }
@@ -86,25 +124,24 @@ impl Term {
     }
 
     pub fn text_via_less(&mut self, text: &str) {
-        self.call(|| {
+        self.call(|| -> Result<(), io::Error> {
             let pager = env::var("PAGER").unwrap_or("less".into());
             let mut command = std::process::Command::new(pager)
                 .stdin(std::process::Stdio::piped())
-                .spawn()
-                .expect("Spawning less failed");
+                .spawn()?;
 
             if let Some(mut stdin) = command.stdin.take() {
-                stdin
-                    .write_all(text.as_bytes())
-                    .expect("Writing to less failed");
+                // pager may be closed before reading whole text
+                match stdin.write_all(text.as_bytes()) {
+                    Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e),
+                    _ => {}
+                }
             }
 
-            command
-                .wait()
-                .map(|_| ())
-                .warn_on_err("Waiting for 'less' to finish failed.");
+            command.wait().map(|_| ())
         })
-        .warn_on_err("Calling 'less' failed.");
+        .and_then(|result| result)
+        .warn_on_err("Calling pager failed.");
     }
 }
 
@@ -0,0 +1,101 @@
+use std::{
+    fs::File,
+    io::{LineWriter, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::warn;
+
+/// Appends JSON lines describing events to a file, see --trace-events.
+/// Description of an event is not even formatted when tracing is disabled.
+#[derive(Debug, Default)]
+pub struct EventTrace {
+    writer: Option<LineWriter<File>>,
+}
+
+impl EventTrace {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self {
+            writer: Some(LineWriter::new(file)),
+        })
+    }
+
+    /// Writes event of given kind, first write failure disables tracing
+    pub fn record(&mut self, kind: &str, describe: impl FnOnce() -> String) {
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis());
+        if let Err(e) = writeln!(writer, "{}", line(time, kind, &describe())) {
+            warn!("Event trace disabled, write failed with: {}", e);
+            self.writer = None;
+        }
+    }
+}
+
+fn line(time: u128, kind: &str, value: &str) -> String {
+    format!(
+        r#"{{"time":{},"kind":{},"value":{}}}"#,
+        time,
+        json_string(kind),
+        json_string(value)
+    )
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod test {
+    use super::{line, EventTrace};
+
+    #[test]
+    fn json_line() {
+        assert_eq!(
+            line(12, "command", "echo \"a\\b\"\n\x1b"),
+            r#"{"time":12,"kind":"command","value":"echo \"a\\b\"\n\u001b"}"#
+        );
+    }
+
+    #[test]
+    fn record() {
+        let mut disabled = EventTrace::default();
+        disabled.record("input", || panic!("formatted while disabled"));
+
+        let path = std::env::temp_dir().join(format!("glog-trace-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut trace = EventTrace::open(&path).unwrap();
+        trace.record("input", || "j".to_owned());
+        trace.record("action", || "Only(\"down\")".to_owned());
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines: Vec<_> = written.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(r#"{"time":"#));
+        assert!(lines[0].ends_with(r#","kind":"input","value":"j"}"#));
+        assert!(lines[1].ends_with(r#","kind":"action","value":"Only(\"down\")"}"#));
+    }
+}
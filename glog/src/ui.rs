@@ -1,4 +1,12 @@
-use crate::app::{self, App, Entry};
+use std::ops::Range;
+
+use crate::{
+    age::Age,
+    app::{self, App, Entry},
+    conventional,
+    graph::{self, GraphColors},
+    oneline::{self, Field, RefKind},
+};
 use ansi_to_tui::IntoText;
 use ratatui::{
     layout::{Constraint, Direction, Layout},
@@ -8,12 +16,267 @@ use ratatui::{
     widgets::{Block, BorderType, Borders, List, ListItem, ListState},
     Frame,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Returns blank of the same display width as selection marker
+fn selection_pad(marker: &str) -> String {
+    " ".repeat(UnicodeWidthStr::width(marker))
+}
+
+/// Drops first `count` columns of spans, wide character cut in half is replaced with a space.
+fn skip_columns(spans: Vec<Span<'_>>, count: usize) -> Vec<Span<'_>> {
+    let mut to_skip = count;
+    spans
+        .into_iter()
+        .filter_map(|span| {
+            if to_skip == 0 {
+                return Some(span);
+            }
+            let mut content = String::new();
+            for c in span.content.chars() {
+                let width = c.width().unwrap_or(0);
+                if to_skip == 0 {
+                    content.push(c);
+                } else if width > to_skip {
+                    content.push_str(&" ".repeat(width - to_skip));
+                    to_skip = 0;
+                } else {
+                    to_skip -= width;
+                }
+            }
+            (!content.is_empty()).then(|| Span::styled(content, span.style))
+        })
+        .collect()
+}
+
+/// Returns color of name (of author or ref), the same name always gets the same color
+fn name_color(name: &str) -> Color {
+    const PALETTE: [Color; 6] = [
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+    ];
+    let hash = name
+        .bytes()
+        .fold(0u32, |hash, b| hash.wrapping_mul(31).wrapping_add(b as u32));
+    PALETTE[hash as usize % PALETTE.len()]
+}
+
+/// Limits spans to `max` columns, when they are wider the last column is replaced with `…`.
+/// Wide character which doesn't fit is replaced with a space.
+fn cap_columns(spans: Vec<Span<'_>>, max: usize) -> Vec<Span<'_>> {
+    let width: usize = spans.iter().map(|s| s.content.width()).sum();
+    if width <= max {
+        return spans;
+    }
+    let mut left = max.saturating_sub(1);
+    let mut capped = Vec::new();
+    for span in spans {
+        if left == 0 {
+            break;
+        }
+        let mut content = String::new();
+        for c in span.content.chars() {
+            let width = c.width().unwrap_or(0);
+            if width > left {
+                content.push_str(&" ".repeat(left));
+                left = 0;
+                break;
+            }
+            content.push(c);
+            left -= width;
+        }
+        if !content.is_empty() {
+            capped.push(Span::styled(content, span.style));
+        }
+    }
+    if max > 0 {
+        capped.push(Span::raw("…"));
+    }
+    capped
+}
+
+/// Longest group label shown, longer ones are cut
+const MAX_GROUP_WIDTH: usize = 20;
+
+/// Returns reached_by label of rows which start a run of rows reached by the same ref, first row
+/// always has one so group of the top row is known. Graph only rows continue previous run.
+fn group_labels<'a>(reached_by: impl Iterator<Item = &'a str>) -> Vec<Option<&'a str>> {
+    let mut last = None;
+    reached_by
+        .enumerate()
+        .map(|(row, name)| {
+            if name.is_empty() || (row > 0 && last == Some(name)) {
+                return None;
+            }
+            last = Some(name);
+            Some(name)
+        })
+        .collect()
+}
+
+/// Group column of a row: label when it starts a group, blank of the column width otherwise
+struct GroupCell<'a> {
+    label: Option<&'a str>,
+    width: usize,
+}
+
+/// Returns byte ranges of non-overlapping matches of query, case is ignored (ASCII only) unless
+/// query has upper-case letters
+fn match_ranges(text: &str, query: &str) -> Vec<Range<usize>> {
+    let ignore_case = !query.chars().any(char::is_uppercase);
+    let (text_bytes, query_bytes) = (text.as_bytes(), query.as_bytes());
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while !query.is_empty() && start + query.len() <= text.len() {
+        let candidate = &text_bytes[start..start + query.len()];
+        let matched = if ignore_case {
+            candidate.eq_ignore_ascii_case(query_bytes)
+        } else {
+            candidate == query_bytes
+        };
+        if matched && text.is_char_boundary(start) {
+            ranges.push(start..start + query.len());
+            start += query.len();
+        } else {
+            start += 1;
+        }
+    }
+    ranges
+}
 
-// TODO: allow to scroll left/right on very long lines
+/// Splits text into spans of style, given ranges get highlight style
+fn highlighted(
+    text: &str,
+    style: Style,
+    ranges: &[Range<usize>],
+    highlight: Style,
+) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for range in ranges {
+        if range.start > last {
+            spans.push(Span::styled(text[last..range.start].to_owned(), style));
+        }
+        spans.push(Span::styled(text[range.clone()].to_owned(), highlight));
+        last = range.end;
+    }
+    if last < text.len() || spans.is_empty() {
+        spans.push(Span::styled(text[last..].to_owned(), style));
+    }
+    spans
+}
+
+/// Moves columns of unscrolled line by horizontal scroll and by width of fixed prefix, None when
+/// nothing of them is left on screen
+fn shifted_columns(columns: Range<usize>, h_offset: usize, prefix: usize) -> Option<Range<usize>> {
+    let start = columns.start.saturating_sub(h_offset) + prefix;
+    let end = columns.end.saturating_sub(h_offset) + prefix;
+    (start < end).then_some(start..end)
+}
+
+/// Character of wrapped line with column it had before wrapping, None for indentation
+#[derive(Debug, Clone, PartialEq)]
+struct Cell {
+    c: char,
+    style: Style,
+    column: Option<usize>,
+}
+
+/// Wraps spans into rows of at most width columns, preferably at spaces. Continuation rows are
+/// indented by indent columns, which is expected to be well below width.
+fn wrap_cells(spans: Vec<Span>, width: usize, indent: usize) -> Vec<Vec<Cell>> {
+    let indentation = || {
+        vec![
+            Cell {
+                c: ' ',
+                style: Style::default(),
+                column: None,
+            };
+            indent
+        ]
+    };
+    let row_width =
+        |row: &[Cell]| -> usize { row.iter().map(|cell| cell.c.width().unwrap_or(0)).sum() };
+    let mut rows = Vec::new();
+    let mut row: Vec<Cell> = Vec::new();
+    let mut column = 0;
+    for span in spans {
+        for c in span.content.chars() {
+            let cell = Cell {
+                c,
+                style: span.style,
+                column: Some(column),
+            };
+            column += c.width().unwrap_or(0);
+            let first_cell = rows.is_empty() && row.is_empty() || row.len() == indent;
+            if first_cell || row_width(&row) + c.width().unwrap_or(0) <= width {
+                row.push(cell);
+                continue;
+            }
+            let min_break = if rows.is_empty() { 1 } else { indent + 1 };
+            let tail = match row.iter().rposition(|cell| cell.c == ' ') {
+                Some(space) if space >= min_break => row.split_off(space + 1),
+                _ => Vec::new(),
+            };
+            rows.push(std::mem::replace(&mut row, indentation()));
+            row.extend(tail);
+            row.push(cell);
+        }
+    }
+    rows.push(row);
+    rows
+}
 
-fn log_line<'a>(entry: &'a Entry, app: &app::App) -> Line<'a> {
+/// Joins cells of the same style into spans
+fn cells_to_line(cells: Vec<Cell>) -> Line<'static> {
+    let mut spans: Vec<(Style, String)> = Vec::new();
+    for cell in cells {
+        match spans.last_mut() {
+            Some((style, text)) if *style == cell.style => text.push(cell.c),
+            _ => spans.push((cell.style, cell.c.to_string())),
+        }
+    }
+    spans
+        .into_iter()
+        .map(|(style, text)| Span::styled(text, style))
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Returns wrapped row and its columns showing given columns of unwrapped line, None when they
+/// are split across rows
+fn wrapped_columns(rows: &[Vec<Cell>], columns: Range<usize>) -> Option<(usize, Range<usize>)> {
+    rows.iter().enumerate().find_map(|(index, row)| {
+        let first = row.iter().position(|c| c.column == Some(columns.start))?;
+        let start: usize = row[..first].iter().map(|c| c.c.width().unwrap_or(0)).sum();
+        let mut end = start;
+        for cell in &row[first..] {
+            if cell.column.is_some_and(|column| column >= columns.end) {
+                break;
+            }
+            end += cell.c.width().unwrap_or(0);
+        }
+        (end - start == columns.len()).then_some((index, start..end))
+    })
+}
+
+/// Returns lines of entry (more than one only when wrapped to given width) and row and columns of
+/// its author within them, if it is shown
+fn log_line<'a>(
+    entry: &'a Entry,
+    app: &app::App,
+    label: Option<char>,
+    group: Option<GroupCell>,
+    wrap: Option<usize>,
+) -> (Vec<Line<'a>>, Option<(usize, Range<usize>)>) {
     // TODO: style as struct
     let hash_style = Style::default().fg(Color::Yellow);
+    let reached_by_style = Style::default().fg(Color::Magenta);
+    let group_style = reached_by_style.add_modifier(Modifier::BOLD);
     let heads_style = Style::default().fg(Color::Green);
     let head_style = heads_style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
     let remotes_style = Style::default().fg(Color::Red);
@@ -21,58 +284,195 @@ fn log_line<'a>(entry: &'a Entry, app: &app::App) -> Line<'a> {
     let parantheses_style = Style::default().fg(Color::Yellow);
     let subject_style = Style::default().fg(Color::White);
     let author_date_style = Style::default().fg(Color::DarkGray);
+    let match_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let jump_label_style = Style::default()
+        .fg(Color::Black)
+        .bg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
 
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let options = oneline::Options {
+        full_hash: app.full_hash,
+        has_selection: app.log.has_selected(),
+        age: app.show_age.then_some((app.age_buckets, now)),
+        reached_by: app.show_reached_by,
+        initials: app.show_initials,
+    };
     let mut spans = Vec::new();
-    if entry.selected() {
-        spans.push(Span::raw("➡️ "));
-    } else if app.log.has_selected() {
-        spans.push(Span::raw("  "));
-    }
-
-    // transform graph
-    let x = entry
-        .git
-        .graph
-        .to_text()
-        .expect("Couldn't transform graph into text");
-    assert_eq!(x.lines.len(), 1);
-    let lines = &x.lines[0];
-    spans.extend(lines.spans.clone());
-
-    spans.push(Span::styled(
-        entry.git.hash.chars().take(8).collect::<String>(),
-        hash_style,
-    ));
-    spans.push(Span::raw(" "));
-    if let Some(refs) = &entry.git.refs {
-        // build iterator of Spans
-        let mut v = Vec::new();
-        if let Some(head) = &refs.head {
-            v.push(Span::styled(head, head_style));
-        }
-        v.extend(refs.heads.iter().map(|v| Span::styled(v, heads_style)));
-        v.extend(
-            refs.remotes
-                .iter()
-                .filter(|v| v.as_str() != "origin/HEAD")
-                .map(|v| Span::styled(v, remotes_style)),
+    let mut author = None;
+    let mut subject_start = 0;
+    for field in oneline::fields(entry, options) {
+        match field {
+            Field::Selection(true) => spans.push(Span::raw(app.selection_marker.clone())),
+            Field::Selection(false) => spans.push(Span::raw(selection_pad(&app.selection_marker))),
+            Field::Age(Some(age)) => {
+                // fades from green to gray with age
+                let color = match age {
+                    Age::Today => Color::Green,
+                    Age::Week => Color::Indexed(71),
+                    Age::Month => Color::Indexed(101),
+                    Age::Older => Color::DarkGray,
+                };
+                spans.push(Span::styled("● ", Style::default().fg(color)));
+            }
+            Field::Age(None) => spans.push(Span::raw("  ")),
+            Field::Initials(initials, author) => {
+                // initials are at most 2 characters, some of them may be wide
+                let pad = 3usize.saturating_sub(initials.width());
+                let style = Style::default()
+                    .fg(name_color(author))
+                    .add_modifier(Modifier::BOLD);
+                spans.push(Span::styled(initials, style));
+                spans.push(Span::raw(" ".repeat(pad)));
+            }
+            Field::Graph(graph) => {
+                let graph_spans = match app.graph_colors {
+                    GraphColors::Glyphs => graph::glyph_spans(graph),
+                    GraphColors::Ansi => match graph.to_text() {
+                        // graph is a single line, anything else is not expected from git
+                        Ok(text) => text
+                            .lines
+                            .into_iter()
+                            .flat_map(|l| l.spans)
+                            .map(|s| Span::styled(s.content.into_owned(), s.style))
+                            .collect(),
+                        Err(_) => vec![Span::raw(graph)],
+                    },
+                };
+                match app.graph_width {
+                    Some(max) => spans.extend(cap_columns(graph_spans, max)),
+                    None => spans.extend(graph_spans),
+                }
+            }
+            Field::Hash(hash) => {
+                spans.push(Span::styled(hash, hash_style));
+                spans.push(Span::raw(" "));
+            }
+            Field::ReachedBy(name) => {
+                spans.push(Span::styled(format!("[{}]", name), reached_by_style));
+                spans.push(Span::raw(" "));
+            }
+            Field::Refs(refs) => {
+                spans.push(Span::styled("(", parantheses_style));
+                for (i, (kind, name)) in refs.into_iter().enumerate() {
+                    if i > 0 {
+                        spans.push(Span::raw(", "));
+                    }
+                    let style = match kind {
+                        RefKind::Head => head_style,
+                        RefKind::Branch => heads_style,
+                        RefKind::Remote => remotes_style,
+                        RefKind::Tag => tags_style,
+                    };
+                    // tags keep their color so they still differ from branches
+                    let style = match kind {
+                        RefKind::Head | RefKind::Branch | RefKind::Remote if app.ref_colors => {
+                            style.fg(name_color(name))
+                        }
+                        _ => style,
+                    };
+                    spans.push(Span::styled(name, style));
+                }
+                spans.push(Span::styled(") ", parantheses_style));
+            }
+            Field::Subject(subject) => {
+                subject_start = spans.iter().map(|s: &Span| s.content.width()).sum();
+                let typed = conventional::split_type(subject).zip(
+                    app.commit_types
+                        .as_deref()
+                        .and_then(|types| conventional::type_color(types, subject)),
+                );
+                let rest = match typed {
+                    Some(((_, prefix, _), color)) => {
+                        let type_style = Style::default().fg(color).add_modifier(Modifier::BOLD);
+                        spans.push(Span::styled(prefix, type_style));
+                        &subject[prefix.len()..]
+                    }
+                    None => subject,
+                };
+                match app.current_query.as_deref() {
+                    Some(query) => {
+                        let ranges = match_ranges(rest, query);
+                        spans.extend(highlighted(rest, subject_style, &ranges, match_style));
+                    }
+                    None => spans.push(Span::styled(rest, subject_style)),
+                }
+                spans.push(Span::raw(" "));
+            }
+            Field::AuthorDate(author_date) => {
+                // author_date is "(author, date)"
+                let start = spans
+                    .iter()
+                    .map(|s: &Span| s.content.width())
+                    .sum::<usize>()
+                    + 1;
+                author = Some(start..start + entry.git.author.width());
+                match app.current_query.as_deref() {
+                    Some(query) => {
+                        // only author is searched, it follows "("
+                        let ranges: Vec<_> = match_ranges(&entry.git.author, query)
+                            .into_iter()
+                            .map(|r| r.start + 1..r.end + 1)
+                            .collect();
+                        spans.extend(highlighted(
+                            &author_date,
+                            author_date_style,
+                            &ranges,
+                            match_style,
+                        ));
+                    }
+                    None => spans.push(Span::styled(author_date, author_date_style)),
+                }
+            }
+        }
+    }
+
+    let mut line = Vec::new();
+    // label stays in place when scrolled horizontally
+    if let Some(label) = label {
+        line.push(Span::styled(label.to_string(), jump_label_style));
+        line.push(Span::raw(" "));
+    }
+    // as does the group column
+    if let Some(GroupCell { label, width }) = group {
+        let capped = cap_columns(vec![Span::raw(label.unwrap_or(""))], width);
+        let used: usize = capped.iter().map(|s| s.content.width()).sum();
+        line.extend(
+            capped
+                .into_iter()
+                .map(|s| Span::styled(s.content.into_owned(), group_style)),
         );
-        v.extend(refs.tags.iter().map(|v| Span::styled(v, tags_style)));
+        line.push(Span::raw(" ".repeat(width - used + 1)));
+    }
+    let prefix = line.iter().map(|s| s.content.width()).sum();
+    // wrapped lines are not scrolled horizontally
+    let h_offset = if wrap.is_some() { 0 } else { app.h_offset };
+    let author = author.and_then(|columns| shifted_columns(columns, h_offset, prefix));
+    line.extend(skip_columns(spans, h_offset));
+    match wrap {
+        None => (vec![line.into()], author.map(|columns| (0, columns))),
+        Some(width) => {
+            // continuation rows start under the subject
+            let indent = std::cmp::min(prefix + subject_start, width / 2);
+            let rows = wrap_cells(line, width, indent);
+            let author = author.and_then(|columns| wrapped_columns(&rows, columns));
+            (rows.into_iter().map(cells_to_line).collect(), author)
+        }
+    }
+}
 
-        if !v.is_empty() {
-            spans.push(Span::styled("(", parantheses_style));
-            spans.extend(v.iter().skip(1).fold(vec![v[0].clone()], |mut acc, v| {
-                acc.push(Span::raw(", "));
-                acc.push(v.clone());
-                acc
-            }));
-            spans.push(Span::styled(") ", parantheses_style));
+/// Returns first of rows to draw so that rows up to the cursor (at pos) fit into height
+fn first_fitting_row(heights: &[usize], pos: usize, height: usize) -> usize {
+    let mut used = 0;
+    for row in (0..=pos).rev() {
+        used += heights[row];
+        if used > height {
+            return std::cmp::min(row + 1, pos);
         }
     }
-    spans.push(Span::styled(&entry.git.subject, subject_style));
-    spans.push(Span::raw(" "));
-    spans.push(Span::styled(entry.git.author_and_date(), author_date_style));
-    spans.into()
+    0
 }
 
 fn draw_list(f: &mut Frame, app: &mut App, chunk: ratatui::layout::Rect) {
@@ -80,9 +480,60 @@ fn draw_list(f: &mut Frame, app: &mut App, chunk: ratatui::layout::Rect) {
 
     app.log.set_view_height(height);
     let (pos, rows) = app.log.iter_view();
-    let rows = rows
-        .map(|entry| ListItem::new(log_line(entry, app)))
-        .collect::<Vec<_>>();
+    let rows: Vec<_> = rows.collect();
+    let groups = if app.group_reached_by {
+        group_labels(rows.iter().map(|e| e.git.reached_by_short()))
+    } else {
+        Vec::new()
+    };
+    let group_width = groups
+        .iter()
+        .flatten()
+        .map(|l| l.width())
+        .max()
+        .unwrap_or(0)
+        .min(MAX_GROUP_WIDTH);
+    let wrap = app.wrap_subjects.then_some(chunk.width as usize);
+    let lines: Vec<_> = rows
+        .iter()
+        .enumerate()
+        .map(|(row, entry)| {
+            let group = app.group_reached_by.then(|| GroupCell {
+                label: groups[row],
+                width: group_width,
+            });
+            log_line(entry, app, app.jump_label(row), group, wrap)
+        })
+        .collect();
+    // view is counted in entries, wrapped ones take more rows so some at the top are left out
+    // to keep cursor on screen
+    let heights: Vec<_> = lines.iter().map(|(lines, _)| lines.len()).collect();
+    let skip = match pos < heights.len() {
+        true => first_fitting_row(&heights, pos, height as usize),
+        false => 0,
+    };
+
+    let mut author_hits = Vec::new();
+    // rows start below the top border
+    let mut y = chunk.y as usize + 1;
+    let mut items = Vec::new();
+    for ((lines, author), entry) in lines.into_iter().zip(&rows).skip(skip) {
+        if let Some((line, columns)) = author {
+            let row = y + line;
+            if row < chunk.bottom() as usize {
+                let x = |column: usize| (chunk.x as usize + column).min(chunk.right() as usize);
+                author_hits.push(app::AuthorHit {
+                    row: row as u16,
+                    columns: x(columns.start) as u16..x(columns.end) as u16,
+                    author: entry.git.author.clone(),
+                });
+            }
+        }
+        y += lines.len();
+        items.push(ListItem::new(lines));
+    }
+    app.author_hits = author_hits;
+    let rows = items;
 
     let list = List::new(rows)
         .block(
@@ -99,7 +550,7 @@ fn draw_list(f: &mut Frame, app: &mut App, chunk: ratatui::layout::Rect) {
         );
 
     let mut state = ListState::default();
-    state.select(Some(pos));
+    state.select(Some(pos - skip));
     f.render_stateful_widget(list, chunk, &mut state);
 }
 
@@ -119,16 +570,223 @@ pub fn draw(f: &mut Frame, app: &mut App) {
     draw_list(f, app, chunks[0]);
 
     let status_style = Style::default().add_modifier(Modifier::REVERSED);
-    let status_block = ratatui::widgets::Paragraph::new("status").style(status_style);
+    let status_text = match app.mode() {
+        app::Mode::Command(_) => app.placeholder_preview(),
+        _ => app
+            .pending_count
+            .map_or_else(String::new, |count| count.to_string()),
+    };
+    let status_text = if status_text.is_empty() {
+        "status".to_owned()
+    } else {
+        status_text
+    };
+    let status_block = ratatui::widgets::Paragraph::new(status_text).style(status_style);
     f.render_widget(status_block, chunks[1]);
 
     match app.mode() {
         app::Mode::Normal => {
-            let block = ratatui::widgets::Paragraph::new(app.status.as_str());
+            let block = match &app.sticky {
+                Some(sticky) => {
+                    ratatui::widgets::Paragraph::new(format!("{} (<Esc> dismisses)", sticky))
+                        .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                }
+                None => ratatui::widgets::Paragraph::new(app.status.as_str()),
+            };
             f.render_widget(block, chunks[2]);
         }
         app::Mode::Command(_cmd) => {
             f.render_widget(app.textarea.widget(), chunks[2]);
         }
+        app::Mode::Jump => {
+            let block = ratatui::widgets::Paragraph::new("Jump to label (<Esc> cancels)");
+            f.render_widget(block, chunks[2]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::{
+        style::{Color, Style},
+        text::{Line, Span},
+    };
+
+    use unicode_width::UnicodeWidthStr;
+
+    use super::{
+        cap_columns, cells_to_line, first_fitting_row, group_labels, highlighted, match_ranges,
+        name_color, selection_pad, shifted_columns, skip_columns, wrap_cells, wrapped_columns,
+    };
+
+    fn contents(spans: Vec<Span>) -> Vec<String> {
+        spans.into_iter().map(|s| s.content.into_owned()).collect()
+    }
+
+    #[test]
+    fn selection_pad_width() {
+        for marker in ["> ", "*", "➡ ", "选 ", "▶▶ "] {
+            let pad = selection_pad(marker);
+            assert_eq!(pad.width(), marker.width(), "marker {:?}", marker);
+            assert!(pad.chars().all(|c| c == ' '));
+        }
+    }
+
+    #[test]
+    fn groups_of_rows() {
+        assert_eq!(
+            group_labels(["main", "main", "", "topic", "main", "main"].into_iter()),
+            [Some("main"), None, None, Some("topic"), Some("main"), None]
+        );
+        // top row always gets the label, graph only rows continue the group
+        assert_eq!(
+            group_labels(["", "main", "", "main"].into_iter()),
+            [None, Some("main"), None, None]
+        );
+        assert!(group_labels(std::iter::empty()).is_empty());
+    }
+
+    #[test]
+    fn cap_columns_of_wide_graph() {
+        use ansi_to_tui::IntoText;
+
+        let graph = "\x1b[31m|\x1b[m \x1b[32m|\x1b[m \x1b[33m|\x1b[m \x1b[34m*\x1b[m ";
+        let spans: Vec<Span> = graph.to_text().unwrap().lines[0].spans.clone();
+        let width = spans.iter().map(|s| s.content.width()).sum::<usize>();
+        assert_eq!(width, 8);
+
+        assert_eq!(cap_columns(spans.clone(), 8), spans);
+        let capped = cap_columns(spans.clone(), 4);
+        assert_eq!(capped.iter().map(|s| s.content.width()).sum::<usize>(), 4);
+        assert_eq!(capped[0].style.fg, Some(Color::Red));
+        assert_eq!(contents(capped).concat(), "| |…");
+        assert_eq!(contents(cap_columns(spans, 1)), ["…"]);
+
+        // wide character is not split
+        let capped = cap_columns(vec![Span::raw("|选|")], 3);
+        assert_eq!(contents(capped), ["| ", "…"]);
+    }
+
+    #[test]
+    fn skip_columns_across_spans() {
+        let style = Style::default().fg(Color::Yellow);
+        let spans = vec![
+            Span::raw("| * "),
+            Span::styled("abc", style),
+            Span::raw(" x"),
+        ];
+        assert_eq!(
+            contents(skip_columns(spans.clone(), 0)),
+            ["| * ", "abc", " x"]
+        );
+        assert_eq!(contents(skip_columns(spans.clone(), 4)), ["abc", " x"]);
+        let skipped = skip_columns(spans.clone(), 5);
+        assert_eq!(skipped[0].style, style);
+        assert_eq!(contents(skipped), ["bc", " x"]);
+        assert!(skip_columns(spans, 100).is_empty());
+    }
+
+    #[test]
+    fn skip_columns_multibyte() {
+        // 'ż' is 2 bytes but 1 column, '日' is 2 columns
+        let spans = vec![Span::raw("żółw"), Span::raw("日本")];
+        assert_eq!(contents(skip_columns(spans.clone(), 2)), ["łw", "日本"]);
+        // wide character cut in half
+        assert_eq!(contents(skip_columns(spans.clone(), 5)), [" 本"]);
+        assert_eq!(contents(skip_columns(spans, 6)), ["本"]);
+    }
+
+    #[test]
+    fn author_columns_follow_scroll() {
+        assert_eq!(shifted_columns(10..15, 0, 0), Some(10..15));
+        assert_eq!(shifted_columns(10..15, 0, 2), Some(12..17));
+        assert_eq!(shifted_columns(10..15, 12, 2), Some(2..5));
+        // scrolled out of view
+        assert_eq!(shifted_columns(10..15, 15, 2), None);
+        assert_eq!(shifted_columns(10..15, 20, 0), None);
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)] // ranges of single match
+    fn search_matches() {
+        assert_eq!(match_ranges("Fix parser, fix lexer", "fix"), [0..3, 12..15]);
+        // upper case in query makes it case sensitive
+        assert_eq!(match_ranges("Fix parser, fix lexer", "Fix"), [0..3]);
+        assert_eq!(match_ranges("aaaa", "aa"), [0..2, 2..4]);
+        assert_eq!(match_ranges("zażółć", "żó"), [2..6]);
+        assert!(match_ranges("abc", "").is_empty());
+        assert!(match_ranges("ab", "abc").is_empty());
+
+        let (style, hl) = (Style::default(), Style::default().bg(Color::Yellow));
+        let spans = highlighted("Fix parser", style, &[0..3], hl);
+        assert_eq!(contents(spans.clone()), ["Fix", " parser"]);
+        assert_eq!((spans[0].style, spans[1].style), (hl, style));
+        assert_eq!(
+            contents(highlighted("a fix", style, &[2..5], hl)),
+            ["a ", "fix"]
+        );
+        assert_eq!(contents(highlighted("abc", style, &[], hl)), ["abc"]);
+        assert_eq!(contents(highlighted("", style, &[], hl)), [""]);
+    }
+
+    #[test]
+    fn wrap_long_subject() {
+        let hash_style = Style::default().fg(Color::Yellow);
+        let spans = vec![
+            Span::raw("* "),
+            Span::styled("d401a57c", hash_style),
+            Span::raw(" "),
+            Span::raw("Fix parser of long subjects (Jane, 2 days ago)"),
+        ];
+        let rows = wrap_cells(spans, 24, 11);
+        let lines: Vec<String> = rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.c).collect())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "* d401a57c Fix parser ",
+                "           of long ",
+                "           subjects ",
+                "           (Jane, 2 ",
+                "           days ago)",
+            ]
+        );
+        assert!(lines.iter().all(|l| l.width() <= 24));
+        // hash keeps its style on the first row
+        let first = cells_to_line(rows[0].clone());
+        assert_eq!(first.spans[1], Span::styled("d401a57c", hash_style));
+
+        // author "Jane" is at columns 40..44 of unwrapped line
+        assert_eq!(wrapped_columns(&rows, 40..44), Some((3, 12..16)));
+        // "2 days" is split across rows
+        assert_eq!(wrapped_columns(&rows, 46..52), None);
+
+        // word longer than width is cut
+        let rows = wrap_cells(vec![Span::raw("abcdefgh")], 4, 1);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(cells_to_line(rows[1].clone()), Line::from(" efg"));
+        // short line is kept
+        assert_eq!(wrap_cells(vec![Span::raw("ab")], 4, 1).len(), 1);
+    }
+
+    #[test]
+    fn rows_fitting_view() {
+        assert_eq!(first_fitting_row(&[1, 1, 1], 2, 5), 0);
+        assert_eq!(first_fitting_row(&[2, 3, 2, 1], 3, 5), 2);
+        assert_eq!(first_fitting_row(&[2, 3, 2, 1], 1, 5), 0);
+        // cursor row alone is higher than view
+        assert_eq!(first_fitting_row(&[1, 9], 1, 5), 1);
+    }
+
+    #[test]
+    fn colors_of_names() {
+        for name in ["origin/main", "upstream/main", "Jane Doe", ""] {
+            // another string with the same content
+            let copy: String = name.chars().collect();
+            assert_eq!(name_color(name), name_color(&copy), "{}", name);
+        }
+        assert_ne!(name_color("origin/main"), name_color("upstream/main"));
     }
 }
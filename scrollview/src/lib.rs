@@ -5,10 +5,21 @@ pub struct View {
     pub end: usize,
 }
 
+/// Policy of scrolling when cursor moves
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ScrollMode {
+    /// cursor moves within view, view scrolls once cursor hits the offset
+    #[default]
+    Offset,
+    /// cursor stays on its row and the view scrolls, until start or end of list is reached
+    FixedCursor,
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct StatefulPosition {
     // currently visible slice
     view: View,
+    mode: ScrollMode,
 
     // value set by user
     user_offset: usize,
@@ -38,10 +49,19 @@ impl StatefulPosition {
         self.view.pos + self.view.start
     }
 
+    pub fn set_mode(&mut self, mode: ScrollMode) {
+        self.mode = mode;
+    }
+
     pub fn next(&mut self, count: usize) {
         if self.length == 0 {
             return;
         }
+        if self.mode == ScrollMode::FixedCursor {
+            let target = std::cmp::min(self.position().saturating_add(count), self.length - 1);
+            self.scroll_fixed(target);
+            return;
+        }
         self.view.pos = std::cmp::min(self.view.pos + count, self.length - 1 - self.view.start);
 
         if let Some(count_to_scroll) = self.view.pos.checked_sub(self.height - self.offset - 1) {
@@ -57,6 +77,10 @@ impl StatefulPosition {
         if self.length == 0 {
             return;
         }
+        if self.mode == ScrollMode::FixedCursor {
+            self.scroll_fixed(self.position().saturating_sub(count));
+            return;
+        }
         let old_pos = self.view.pos;
         self.view.pos = self.view.pos.saturating_sub(count);
         self.view.start = self
@@ -121,6 +145,14 @@ impl StatefulPosition {
         }
     }
 
+    /// Moves cursor to target keeping its row, view is kept within list bounds
+    fn scroll_fixed(&mut self, target: usize) {
+        let max_start = self.length.saturating_sub(self.height);
+        self.view.start = std::cmp::min(target.saturating_sub(self.view.pos), max_start);
+        self.view.pos = target - self.view.start;
+        self.view.end = self.view.start + self.height;
+    }
+
     fn update_offset(&mut self) {
         self.offset = if 2 * self.user_offset > self.height {
             self.height.checked_div(2).unwrap_or(0)
@@ -129,25 +161,32 @@ impl StatefulPosition {
         }
     }
 
+    /// Scrolls so that cursor is in the middle of view.
+    /// Near the top cursor stays above the middle as there's nothing to scroll,
+    /// near the bottom view is padded after last element.
+    /// Afterwards `view.end == view.start + height` always holds (before clamping by length).
     pub fn center(&mut self) {
         if self.height == 0 {
             return;
         }
+        let position = self.position();
         let middle = self.height / 2;
-        if let Some(count) = self.view.pos.checked_sub(middle) {
-            self.view.pos = middle;
-            self.view.start += count;
-            self.view.end += count;
-        } else {
-            let count = middle - self.view.pos;
+        self.view.start = position.saturating_sub(middle);
+        self.view.pos = position - self.view.start;
+        self.view.end = self.view.start + self.height;
+    }
 
-            let old_start = self.view.start;
-            self.view.start = self.view.start.saturating_sub(count);
-            let real_count = old_start - self.view.start;
-            self.view.pos += real_count;
-            self.view.end -= real_count;
+    /// Scrolls so that cursor is in the first row of view, near the bottom view is padded after
+    /// last element as with center.
+    pub fn align_top(&mut self) {
+        if self.height == 0 {
+            return;
         }
+        self.view.start = self.position();
+        self.view.pos = 0;
+        self.view.end = self.view.start + self.height;
     }
+
     /// Returns position as seen in current view or None if not in view.
     pub fn view_position(&self, position: usize) -> Option<usize> {
         let pos = position.checked_sub(position)?;
@@ -161,7 +200,7 @@ impl StatefulPosition {
 
 #[cfg(test)]
 mod test {
-    use super::{StatefulPosition, View};
+    use super::{ScrollMode, StatefulPosition, View};
 
     macro_rules! assert_pos {
         ($current:ident, $slice_pos:expr, $slice_start:expr) => {
@@ -333,4 +372,129 @@ mod test {
         current.center();
         assert_pos!(current, 10, 15);
     }
+
+    macro_rules! assert_centered_invariant {
+        ($current:ident) => {
+            assert_eq!($current.view.end, $current.view.start + $current.height);
+        };
+    }
+
+    #[test]
+    fn center_near_top() {
+        let mut current = StatefulPosition::default();
+        current.reset(5, 40);
+        current.set_height(20);
+        current.select(3);
+        current.center();
+        assert_centered_invariant!(current);
+        assert_pos!(current, 3, 0);
+
+        // scrolled a bit so only part of view can be scrolled back
+        current.select(25);
+        current.prev(18);
+        assert_eq!(current.position(), 7);
+        current.center();
+        assert_centered_invariant!(current);
+        assert_pos!(current, 7, 0);
+
+        current.select(10);
+        current.center();
+        assert_centered_invariant!(current);
+        assert_pos!(current, 10, 0);
+    }
+
+    #[test]
+    fn center_near_bottom() {
+        let mut current = StatefulPosition::default();
+        current.reset(5, 40);
+        current.set_height(20);
+        current.end();
+        assert_eq!(current.position(), 39);
+        current.center();
+        assert_centered_invariant!(current);
+        assert_pos!(current, 10, 29);
+
+        current.start();
+        current.select(33);
+        current.center();
+        assert_centered_invariant!(current);
+        assert_pos!(current, 10, 23);
+    }
+
+    #[test]
+    fn align_top() {
+        let mut current = StatefulPosition::default();
+        current.reset(5, 40);
+        current.set_height(20);
+        current.select(25);
+        current.align_top();
+        assert_centered_invariant!(current);
+        assert_pos!(current, 0, 25);
+        current.select(3);
+        current.align_top();
+        assert_pos!(current, 0, 3);
+    }
+
+    #[test]
+    fn center_short_list() {
+        let mut current = StatefulPosition::default();
+        current.reset(5, 5);
+        current.set_height(20);
+        current.end();
+        current.center();
+        assert_centered_invariant!(current);
+        assert_pos!(current, 4, 0);
+    }
+
+    #[test]
+    fn fixed_cursor() {
+        let mut offset = StatefulPosition::default();
+        offset.reset(5, 40);
+        offset.set_height(20);
+        let mut fixed = offset;
+        fixed.set_mode(ScrollMode::FixedCursor);
+
+        macro_rules! both {
+            ($action:ident($count:expr), $offset:expr, $fixed:expr) => {
+                offset.$action($count);
+                fixed.$action($count);
+                assert_pos!(offset, $offset.0, $offset.1);
+                assert_pos!(fixed, $fixed.0, $fixed.1);
+            };
+        }
+
+        // fixed cursor scrolls right away, offset one moves within the view
+        both!(next(1), (1, 0), (0, 1));
+        both!(next(10), (11, 0), (0, 11));
+        // further down offset starts scrolling too
+        both!(next(5), (14, 2), (0, 16));
+        // fixed cursor reaches end of list so the cursor has to move
+        both!(next(10), (14, 12), (6, 20));
+        both!(next(100), (19, 20), (19, 20));
+        // on the way back cursor stays on last row until start of list
+        both!(prev(3), (16, 20), (19, 17));
+        both!(prev(10), (6, 20), (19, 7));
+        both!(prev(10), (4, 12), (16, 0));
+        both!(prev(100), (0, 0), (0, 0));
+
+        // jumps keep the row too
+        fixed.select(8);
+        assert_pos!(fixed, 0, 8);
+        fixed.next(3);
+        assert_pos!(fixed, 0, 11);
+    }
+
+    #[test]
+    fn fixed_cursor_short_list() {
+        let mut current = StatefulPosition::default();
+        current.set_mode(ScrollMode::FixedCursor);
+        current.reset(5, 5);
+        current.set_height(20);
+        current.next(2);
+        assert_pos!(current, 2, 0);
+        current.next(10);
+        assert_pos!(current, 4, 0);
+        current.prev(1);
+        assert_pos!(current, 3, 0);
+    }
 }
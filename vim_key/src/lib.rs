@@ -3,7 +3,7 @@ use std::{
     fmt::{Debug, Display},
 };
 
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, ModifierKeyCode};
 use pest::{self, iterators::Pair, Parser};
 
 #[derive(pest_derive::Parser, Debug)]
@@ -20,6 +20,7 @@ pub fn vim_key(binding: &'_ str) -> Vec<KeyEvent> {
                     Rule::fx_key => parse_fx_key(p),
                     Rule::mod_key => parse_mod_key(p),
                     Rule::space => KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE),
+                    Rule::named_key => KeyEvent::new(parse_named_key(p), KeyModifiers::NONE),
                     _ => unreachable!(),
                 },
             ),
@@ -107,16 +108,33 @@ pub fn to_vim_key(key_event: KeyEvent) -> String {
             wrap_modifiers("Esc".into(), modifiers)
         }
 
-        key!(C::Null) => todo!("NULL not supported"),
-        key!(C::CapsLock) => todo!("CapsLock not supported"),
-        key!(C::ScrollLock) => todo!("ScrollLock not supported"),
-        key!(C::NumLock) => todo!("NumLock not supported"),
-        key!(C::PrintScreen) => todo!("PrintScreen not supported"),
-        key!(C::Pause) => todo!("Pause not supported"),
-        key!(C::Menu) => todo!("Menu not supported"),
-        key!(C::KeypadBegin) => todo!("Keypad not supported"),
-        key!(C::Media(_)) => todo!("Media not supported"),
-        key!(C::Modifier(_)) => todo!("Single modifier not supported"),
+        // keys below are unusual as bindings so these are mostly for display purposes
+        key!(C::Null, modifiers) => wrap_modifiers("Nul".into(), modifiers),
+        key!(C::CapsLock, modifiers) => wrap_modifiers("CapsLock".into(), modifiers),
+        key!(C::ScrollLock, modifiers) => wrap_modifiers("ScrollLock".into(), modifiers),
+        key!(C::NumLock, modifiers) => wrap_modifiers("NumLock".into(), modifiers),
+        key!(C::PrintScreen, modifiers) => wrap_modifiers("PrintScreen".into(), modifiers),
+        key!(C::Pause, modifiers) => wrap_modifiers("Pause".into(), modifiers),
+        key!(C::Menu, modifiers) => wrap_modifiers("Menu".into(), modifiers),
+        key!(C::KeypadBegin, modifiers) => wrap_modifiers("kBegin".into(), modifiers),
+        key!(C::Media(media), modifiers) => wrap_modifiers(format!("{:?}", media), modifiers),
+        // lone modifier is rendered as modifier prefix without a key eg. <C->
+        key!(C::Modifier(modifier)) => format!("<{}->", modifier_prefix(modifier)),
+    }
+}
+
+/// Returns vim-like prefix for a modifier key, left and right variants are not distinguished.
+fn modifier_prefix(modifier: ModifierKeyCode) -> &'static str {
+    use ModifierKeyCode as K;
+    match modifier {
+        K::LeftShift | K::RightShift => "S",
+        K::LeftControl | K::RightControl => "C",
+        K::LeftAlt | K::RightAlt => "M",
+        K::LeftSuper | K::RightSuper => "D",
+        K::LeftHyper | K::RightHyper => "H",
+        K::LeftMeta | K::RightMeta => "T",
+        K::IsoLevel3Shift => "L3",
+        K::IsoLevel5Shift => "L5",
     }
 }
 
@@ -153,6 +171,19 @@ fn parse_mod_key(p: Pair<Rule>) -> KeyEvent {
     )
 }
 
+fn parse_named_key(p: Pair<Rule>) -> KeyCode {
+    match p.as_str().to_lowercase().as_str() {
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "esc" => KeyCode::Esc,
+        _ => unreachable!(),
+    }
+}
+
 fn parse_key(p: Pair<Rule>) -> KeyCode {
     // TODO: not full implementation
     KeyCode::Char(p.as_str().chars().next().unwrap())
@@ -251,6 +282,11 @@ where
 pub struct VimKeyParser<T> {
     map: InnerMap<T>,
     multi_key: Vec<KeyEvent>,
+    /// count typed before binding eg. 3 in 3j
+    count: Option<usize>,
+    count_enabled: bool,
+    /// letters match bindings regardless of their case
+    fold_case: bool,
 }
 
 impl<T> Default for VimKeyParser<T> {
@@ -258,6 +294,9 @@ impl<T> Default for VimKeyParser<T> {
         Self {
             map: Default::default(),
             multi_key: Vec::default(),
+            count: None,
+            count_enabled: false,
+            fold_case: false,
         }
     }
 }
@@ -265,11 +304,25 @@ impl<T> Default for VimKeyParser<T> {
 #[derive(PartialEq, Debug)]
 pub enum ParsedAction<T> {
     Only(T),
+    /// Action preceded by a count
+    Counted(usize, T),
     Ambiguous(T),
     Partial,
     None,
 }
 
+/// Returns digit for keys which may be a part of count
+fn count_digit(key: &KeyEvent) -> Option<usize> {
+    match key {
+        KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE,
+            ..
+        } => c.to_digit(10).map(|d| d as usize),
+        _ => None,
+    }
+}
+
 impl<T> VimKeyParser<T>
 where
     T: Clone + Display + PartialEq + Debug,
@@ -291,26 +344,72 @@ where
         self.map.remove_action(&keys);
     }
 
+    /// When enabled digits typed before a binding build up a count, unless digit itself is bound
+    /// and no count is pending yet (so bindings like "0" still work).
+    pub fn enable_count(&mut self, enabled: bool) -> &mut Self {
+        self.count_enabled = enabled;
+        self.count = None;
+        self
+    }
+
+    /// When enabled a letter matches binding of either case, exact case is preferred when both
+    /// are bound. Off by default as bindings like g and G are usually distinct.
+    pub fn fold_case(&mut self, enabled: bool) -> &mut Self {
+        self.fold_case = enabled;
+        self
+    }
+
+    /// Returns node for key, with case folding also for the other case of a letter
+    fn child<'m>(&self, map: &'m InnerMap<T>, key: &KeyEvent) -> Option<&'m InnerMap<T>> {
+        map.map.get(key).or_else(|| {
+            let KeyCode::Char(c) = key.code else {
+                return None;
+            };
+            if !self.fold_case || !c.is_alphabetic() {
+                return None;
+            }
+            let mut modifiers = key.modifiers;
+            modifiers.remove(KeyModifiers::SHIFT);
+            c.to_lowercase()
+                .chain(c.to_uppercase())
+                .filter(|&other| other != c)
+                .find_map(|other| map.map.get(&KeyEvent::new(KeyCode::Char(other), modifiers)))
+        })
+    }
+
     pub fn handle_action(&mut self, key: KeyEvent) -> ParsedAction<T> {
+        if self.count_enabled && self.multi_key.is_empty() {
+            if let Some(digit) = count_digit(&key) {
+                let bound = self.map.map.contains_key(&key);
+                if self.count.is_some() || (!bound && digit != 0) {
+                    let count = self.count.unwrap_or(0);
+                    self.count = Some(count.saturating_mul(10).saturating_add(digit));
+                    return ParsedAction::Partial;
+                }
+            }
+        }
+
         let had_multi_key = !self.multi_key.is_empty();
         self.multi_key.push(key);
         let most_inner_map = self
             .multi_key
             .iter()
-            .try_fold(&self.map, |acc, key| acc.map.get(key));
+            .try_fold(&self.map, |acc, key| self.child(acc, key));
         if let Some(map) = most_inner_map {
             if let Some(action) = &map.action {
                 if !map.map.is_empty() {
                     return ParsedAction::Ambiguous(action.clone());
                 } else {
+                    let action = action.clone();
                     self.multi_key.clear();
-                    return ParsedAction::Only(action.clone());
+                    return self.with_count(action);
                 }
             } else {
                 return ParsedAction::Partial;
             }
         } else {
             self.multi_key.clear();
+            self.count = None;
             if had_multi_key {
                 // try once more with clear state
                 return self.handle_action(key);
@@ -319,6 +418,77 @@ where
         ParsedAction::None
     }
 
+    /// Feeds every key of binding string (same syntax as add_action) through handle_action
+    /// and returns result for each of them.
+    pub fn feed(&mut self, keys: &str) -> Vec<ParsedAction<T>> {
+        vim_key(keys)
+            .into_iter()
+            .map(|key| self.handle_action(key))
+            .collect()
+    }
+
+    /// Resolves pending state when no more keys came in time.
+    /// Ambiguous binding resolves into its action (with count if any), partial binding is
+    /// dropped. A pending count without a binding is discarded.
+    pub fn resolve_timeout(&mut self) -> ParsedAction<T> {
+        let action = self
+            .multi_key
+            .iter()
+            .try_fold(&self.map, |acc, key| self.child(acc, key))
+            .and_then(|map| map.action.clone());
+        self.multi_key.clear();
+        match action {
+            Some(action) => self.with_count(action),
+            None => {
+                self.count = None;
+                ParsedAction::None
+            }
+        }
+    }
+
+    /// Returns true when parser waits for more keys
+    pub fn is_pending(&self) -> bool {
+        !self.multi_key.is_empty() || self.count.is_some()
+    }
+
+    /// Drops half-entered keys and count so next key starts a new sequence
+    pub fn reset_pending(&mut self) {
+        self.multi_key.clear();
+        self.count = None;
+    }
+
+    pub fn pending_count(&self) -> Option<usize> {
+        self.count
+    }
+
+    fn with_count(&mut self, action: T) -> ParsedAction<T> {
+        match self.count.take() {
+            Some(count) => ParsedAction::Counted(count, action),
+            None => ParsedAction::Only(action),
+        }
+    }
+
+    /// Returns action bound to keys, matched the same way as handle_action does (including
+    /// fold_case), pending state of parser is not touched
+    pub fn lookup(&self, keys: &[KeyEvent]) -> Option<&T> {
+        self.node(keys).and_then(|map| map.action.as_ref())
+    }
+
+    /// Returns true when more keys could follow binding, i.e. some longer binding starts with it
+    pub fn is_prefix(&self, binding: &str) -> bool {
+        self.is_prefix_keys(&vim_key(binding))
+    }
+
+    /// The same as is_prefix for already parsed keys
+    pub fn is_prefix_keys(&self, keys: &[KeyEvent]) -> bool {
+        self.node(keys).is_some_and(|map| !map.map.is_empty())
+    }
+
+    fn node(&self, keys: &[KeyEvent]) -> Option<&InnerMap<T>> {
+        keys.iter()
+            .try_fold(&self.map, |acc, key| self.child(acc, key))
+    }
+
     pub fn get_actions(&self) -> Vec<(String, &T)> {
         assert_eq!(None, self.map.action, "Action for map root (no key bound)");
         self.map.flatten_actions("")
@@ -370,6 +540,123 @@ mod test {
         );
     }
 
+    #[test]
+    fn named_keys() {
+        assert_eq!(
+            vim_key("<left><Right><home>"),
+            vec![
+                key!(KeyCode::Left, KeyModifiers::NONE),
+                key!(KeyCode::Right, KeyModifiers::NONE),
+                key!(KeyCode::Home, KeyModifiers::NONE),
+            ]
+        );
+        assert_eq!(to_vim_key(vim_key("<end>")[0]), "<End>");
+        assert_eq!(to_vim_key(vim_key("<esc>")[0]), "<Esc>");
+    }
+
+    #[test]
+    fn lookup() {
+        let mut parser = VimKeyParser::default();
+        parser
+            .add_action("gg", 1)
+            .add_action("g", 2)
+            .add_action("zf", 3);
+        parser.handle_action(key!('z'));
+
+        assert_eq!(parser.lookup(&vim_key("gg")), Some(&1));
+        assert_eq!(parser.lookup(&vim_key("g")), Some(&2));
+        assert!(parser.is_prefix_keys(&vim_key("g")));
+        assert_eq!(parser.lookup(&vim_key("z")), None);
+        assert_eq!(parser.lookup(&vim_key("x")), None);
+        assert_eq!(parser.lookup(&[]), None);
+
+        // pending z is still there
+        assert_eq!(parser.handle_action(key!('f')), ParsedAction::Only(3));
+
+        assert_eq!(parser.lookup(&vim_key("GG")), None);
+        parser.fold_case(true);
+        assert_eq!(parser.lookup(&vim_key("GG")), Some(&1));
+        assert!(parser.is_prefix_keys(&vim_key("G")));
+        assert!(parser.is_prefix("Z"));
+    }
+
+    #[test]
+    fn reset_pending() {
+        let mut parser = VimKeyParser::default();
+        parser.enable_count(true);
+        parser.add_action("gg", 1).add_action("j", 2);
+
+        assert_eq!(
+            parser.feed("3g"),
+            [ParsedAction::Partial, ParsedAction::Partial]
+        );
+        assert!(parser.is_pending());
+        parser.reset_pending();
+        assert!(!parser.is_pending());
+        assert_eq!(parser.pending_count(), None);
+        // without reset this would be an invalid "gj"
+        assert_eq!(parser.feed("j"), [ParsedAction::Only(2)]);
+        assert_eq!(
+            parser.feed("gg"),
+            [ParsedAction::Partial, ParsedAction::Only(1)]
+        );
+    }
+
+    #[test]
+    fn fold_case() {
+        let mut parser = VimKeyParser::default();
+        parser
+            .add_action("g", 1)
+            .add_action("zf", 2)
+            .add_action("<c-w>", 3);
+        let shifted = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::SHIFT);
+
+        // off by default
+        assert_eq!(parser.handle_action(shifted('G')), ParsedAction::None);
+        assert_eq!(parser.feed("Zf"), [ParsedAction::None, ParsedAction::None]);
+        assert_eq!(parser.feed("<c-W>"), [ParsedAction::None]);
+
+        parser.fold_case(true);
+        assert_eq!(parser.handle_action(shifted('G')), ParsedAction::Only(1));
+        assert_eq!(parser.handle_action(key!('G')), ParsedAction::Only(1));
+        assert_eq!(
+            parser.feed("ZF"),
+            [ParsedAction::Partial, ParsedAction::Only(2)]
+        );
+        assert_eq!(parser.feed("<c-W>"), [ParsedAction::Only(3)]);
+        assert_eq!(parser.handle_action(key!('x')), ParsedAction::None);
+
+        // exact case wins when both are bound
+        parser.add_action("G", 4);
+        assert_eq!(parser.handle_action(key!('G')), ParsedAction::Only(4));
+        assert_eq!(parser.handle_action(key!('g')), ParsedAction::Only(1));
+
+        parser.fold_case(false);
+        parser.remove_action("G");
+        assert_eq!(parser.handle_action(key!('G')), ParsedAction::None);
+    }
+
+    #[test]
+    fn is_prefix() {
+        let mut parser = VimKeyParser::default();
+        parser
+            .add_action("gg", 1)
+            .add_action("g", 2)
+            .add_action("zf", 3)
+            .add_action("<c-w>h", 4);
+        // leaf
+        assert!(!parser.is_prefix("gg"));
+        assert!(!parser.is_prefix("zf"));
+        // internal node, with and without own action
+        assert!(parser.is_prefix("g"));
+        assert!(parser.is_prefix("z"));
+        assert!(parser.is_prefix("<c-w>"));
+        // unbound
+        assert!(!parser.is_prefix("x"));
+        assert!(!parser.is_prefix("zfz"));
+        assert!(!parser.is_prefix_keys(&vim_key("gx")));
+    }
+
     #[test]
     fn modifiers() {
         assert_eq!(
@@ -409,6 +696,131 @@ mod test {
         assert_eq!(ParsedAction::Only(10), parser.handle_action(key!('0')));
     }
 
+    #[test]
+    fn test_vim_key_parser_feed() {
+        let mut parser = VimKeyParser::default();
+        parser
+            .add_action("gg", "top")
+            .add_action("1", "one")
+            .add_action("10", "ten")
+            .add_action("<c-d>", "down");
+        assert_eq!(
+            parser.feed("gg"),
+            vec![ParsedAction::Partial, ParsedAction::Only("top")]
+        );
+        assert_eq!(
+            parser.feed("10"),
+            vec![ParsedAction::Ambiguous("one"), ParsedAction::Only("ten")]
+        );
+        // ambiguous sequence stays pending until resolved
+        assert_eq!(parser.feed("1"), vec![ParsedAction::Ambiguous("one")]);
+        assert!(parser.is_pending());
+        assert_eq!(parser.resolve_timeout(), ParsedAction::Only("one"));
+        assert_eq!(
+            parser.feed("x<c-d>"),
+            vec![ParsedAction::None, ParsedAction::Only("down")]
+        );
+
+        let mut parser = VimKeyParser::default();
+        parser.enable_count(true).add_action("j", "down");
+        assert_eq!(
+            parser.feed("10j"),
+            vec![
+                ParsedAction::Partial,
+                ParsedAction::Partial,
+                ParsedAction::Counted(10, "down")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vim_key_parser_count() {
+        let mut parser = VimKeyParser::default();
+        parser
+            .enable_count(true)
+            .add_action("j", 1)
+            .add_action("gg", 2);
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('1')));
+        assert_eq!(Some(1), parser.pending_count());
+        // 0 is a part of count once count started
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('0')));
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('5')));
+        assert_eq!(Some(105), parser.pending_count());
+        assert!(parser.is_pending());
+        assert_eq!(
+            ParsedAction::Counted(105, 1),
+            parser.handle_action(key!('j'))
+        );
+        assert_eq!(None, parser.pending_count());
+        assert!(!parser.is_pending());
+        // count is not carried over
+        assert_eq!(ParsedAction::Only(1), parser.handle_action(key!('j')));
+
+        // count with multi key binding
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('3')));
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('g')));
+        assert_eq!(ParsedAction::Counted(3, 2), parser.handle_action(key!('g')));
+
+        // unbound key discards count
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('3')));
+        assert_eq!(ParsedAction::None, parser.handle_action(key!('x')));
+        assert_eq!(None, parser.pending_count());
+    }
+
+    #[test]
+    fn test_vim_key_parser_count_bound_digit() {
+        let mut parser = VimKeyParser::default();
+        parser
+            .enable_count(true)
+            .add_action("0", 0)
+            .add_action("j", 1);
+        // bound digit without pending count is an action
+        assert_eq!(ParsedAction::Only(0), parser.handle_action(key!('0')));
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('2')));
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('0')));
+        assert_eq!(
+            ParsedAction::Counted(20, 1),
+            parser.handle_action(key!('j'))
+        );
+    }
+
+    #[test]
+    fn test_vim_key_parser_timeout() {
+        let mut parser = VimKeyParser::default();
+        parser
+            .enable_count(true)
+            .add_action("d", 1)
+            .add_action("dr", 2)
+            .add_action("gg", 3);
+
+        // ambiguous resolves into shorter binding
+        assert_eq!(ParsedAction::Ambiguous(1), parser.handle_action(key!('d')));
+        assert!(parser.is_pending());
+        assert_eq!(ParsedAction::Only(1), parser.resolve_timeout());
+        assert!(!parser.is_pending());
+
+        // partial is dropped
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('g')));
+        assert_eq!(ParsedAction::None, parser.resolve_timeout());
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('g')));
+        assert_eq!(ParsedAction::Only(3), parser.handle_action(key!('g')));
+
+        // pending count without binding is discarded
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('4')));
+        assert_eq!(ParsedAction::None, parser.resolve_timeout());
+        assert_eq!(None, parser.pending_count());
+        assert_eq!(ParsedAction::Ambiguous(1), parser.handle_action(key!('d')));
+        assert_eq!(ParsedAction::Only(2), parser.handle_action(key!('r')));
+
+        // count is kept for ambiguous binding
+        assert_eq!(ParsedAction::Partial, parser.handle_action(key!('2')));
+        assert_eq!(ParsedAction::Ambiguous(1), parser.handle_action(key!('d')));
+        assert_eq!(ParsedAction::Counted(2, 1), parser.resolve_timeout());
+
+        // nothing pending
+        assert_eq!(ParsedAction::None, parser.resolve_timeout());
+    }
+
     #[test]
     fn test_to_vim_key() {
         use KeyCode as K;
@@ -426,4 +838,46 @@ mod test {
         assert_eq!(to_vim_key(key!(K::Up, ctrl | alt | shift)), "<M-C-S-Up>");
         assert_eq!(to_vim_key(key!(K::Up, ctrl | shift)), "<C-S-Up>");
     }
+
+    #[test]
+    fn test_to_vim_key_keypad_and_modifiers() {
+        use crossterm::event::{MediaKeyCode, ModifierKeyCode as M};
+        use KeyCode as K;
+        let none = KeyModifiers::NONE;
+        assert_eq!(to_vim_key(key!(K::KeypadBegin, none)), "<kBegin>");
+        assert_eq!(
+            to_vim_key(key!(K::KeypadBegin, KeyModifiers::CONTROL)),
+            "<C-kBegin>"
+        );
+        assert_eq!(
+            to_vim_key(key!(K::Media(MediaKeyCode::Play), none)),
+            "<Play>"
+        );
+        assert_eq!(to_vim_key(key!(K::CapsLock, none)), "<CapsLock>");
+
+        let modifiers = [
+            (M::LeftShift, "<S->"),
+            (M::LeftControl, "<C->"),
+            (M::LeftAlt, "<M->"),
+            (M::LeftSuper, "<D->"),
+            (M::LeftHyper, "<H->"),
+            (M::LeftMeta, "<T->"),
+            (M::RightShift, "<S->"),
+            (M::RightControl, "<C->"),
+            (M::RightAlt, "<M->"),
+            (M::RightSuper, "<D->"),
+            (M::RightHyper, "<H->"),
+            (M::RightMeta, "<T->"),
+            (M::IsoLevel3Shift, "<L3->"),
+            (M::IsoLevel5Shift, "<L5->"),
+        ];
+        for (modifier, expected) in modifiers {
+            // modifier key events carry their own modifier flag too
+            assert_eq!(
+                to_vim_key(key!(K::Modifier(modifier), KeyModifiers::SHIFT)),
+                expected
+            );
+            assert_eq!(to_vim_key(key!(K::Modifier(modifier), none)), expected);
+        }
+    }
 }